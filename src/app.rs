@@ -1,71 +1,292 @@
+use std::path::Path;
 use std::time::Duration;
 
-use crate::grid::Grid;
+use crate::export;
+use crate::grid::{self, Grid};
+use crate::history::{self, History};
+use crate::library::Library;
+use crate::minimap::Minimap;
+use crate::patterns::{self, Pattern, Transform};
+use crate::rule::Rule;
+use crate::session;
+use crate::timeline::{self, Timeline};
+use crate::topology::Topology;
+use crate::universe::{Engine, Region, Universe};
 
 pub const MAX_RECORDING_FRAMES: usize = 500;
+/// Cells held across all frames of a recording before it is saved early.
+const MAX_RECORDED_CELLS: usize = 1 << 25;
+/// Grids with more cells than this record the area on screen instead.
+const MAX_RECORDING_AREA: usize = 256 * 256;
+pub const DEFAULT_DENSITY: f64 = 0.25;
+pub const DEFAULT_SOUP_SIZE: usize = 16;
+pub const DEFAULT_GRID_SIZE: usize = 256;
+pub const MAX_GRID_SIZE: usize = 4096;
+/// Range of the delay between steps, in milliseconds.
+pub const MIN_TICK_MS: u64 = 10;
+pub const MAX_TICK_MS: u64 = 1000;
+/// Largest step size exponent for engines that step one generation at a time.
+pub const MAX_STEP_EXP: u32 = 10;
+/// Largest step size exponent for HashLife.
+pub const MAX_HASHLIFE_STEP_EXP: u32 = 48;
+
+/// Zoom levels: -1 draws 2×4 cells per character with braille dots, 0 draws
+/// 1×2 cells with half blocks and N ≥ 1 draws each cell N rows high.
+pub const MIN_ZOOM: i32 = -1;
+pub const MAX_ZOOM: i32 = 8;
+
+/// Terminal columns taken by the pattern library panel.
+pub const LIBRARY_COLUMNS: usize = 34;
+
+/// A mouse drag in progress.
+pub enum Drag {
+    /// Moving the viewport; the last terminal position the view moved to.
+    Pan { column: u16, row: u16 },
+    /// Drawing (`age` 1) or erasing (`age` 0) cells, remembering what changed
+    /// so the whole stroke is undone at once.
+    Paint {
+        age: u16,
+        last: Option<(isize, isize)>,
+        before: Vec<(isize, isize, u16)>,
+        after: Vec<(isize, isize, u16)>,
+    },
+    /// Dragging the view rectangle around the minimap.
+    Minimap,
+}
+
+/// Where a random soup is generated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SoupArea {
+    Grid,
+    Viewport,
+    /// An N×N square centered on the grid (or the origin of an infinite plane),
+    /// with everything else cleared.
+    Centered(usize),
+    /// A selected rectangle, leaving the cells around it alone.
+    Selection(Region),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum PromptKind {
+    Rule,
+    OpenPattern,
+    SavePattern,
+    SaveSession,
+    LoadSession,
+    Seed,
+    GoTo,
+}
+
+impl PromptKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            PromptKind::Rule => "Rule",
+            PromptKind::OpenPattern => "Open pattern",
+            PromptKind::SavePattern => "Save pattern",
+            PromptKind::SaveSession => "Save session",
+            PromptKind::LoadSession => "Load session",
+            PromptKind::Seed => "Seed",
+            PromptKind::GoTo => "Go to x,y",
+        }
+    }
+}
+
+/// Single-line text input shown in the status bar.
+pub struct Prompt {
+    pub kind: PromptKind,
+    pub input: String,
+}
 
 pub struct App {
-    pub grid: Grid,
+    pub universe: Box<dyn Universe>,
     pub paused: bool,
     pub generation: usize,
-    pub cursor_x: usize,
-    pub cursor_y: usize,
+    pub cursor_x: isize,
+    pub cursor_y: isize,
     pub tick_rate: Duration,
     pub cursor_visible: bool,
-    pub pattern_mode: bool,
+    pub patterns: Vec<Pattern>,
+    /// Index of the pattern `p` picks up.
+    pub last_pattern: usize,
+    pub library: Library,
+    /// Whether the library panel is shown and takes key presses.
+    pub library_open: bool,
+    /// Pattern waiting to be stamped at the cursor, previewed as a ghost.
+    pub placing: Option<Pattern>,
+    /// Orientation applied to patterns when they are stamped.
+    pub transform: Transform,
+    pub drag: Option<Drag>,
+    /// Corner of the selection opposite the cursor, while selecting.
+    pub selection_anchor: Option<(isize, isize)>,
+    /// Cells last copied or cut from a selection.
+    pub clipboard: Option<Pattern>,
+    /// Whether the braille overview of the universe is drawn over the grid.
+    pub show_minimap: bool,
     pub view_width: usize,
     pub view_height: usize,
-    pub viewport_x: usize,
-    pub viewport_y: usize,
+    pub viewport_x: isize,
+    pub viewport_y: isize,
     pub zoom: i32,
+    pub density: f64,
+    pub soup_area: SoupArea,
+    pub soup_size: usize,
+    /// Size of the finite grid to return to when leaving an infinite plane.
+    pub grid_size: (usize, usize),
+    /// Each step advances 2^`step_exp` generations.
+    pub step_exp: u32,
+    /// Whether cells are colored by age; the grid steps faster without ages.
+    pub show_ages: bool,
+    /// Threads the engine may use to step.
+    pub threads: usize,
+    /// Debug overlay highlighting the tiles the engine computed in the last step.
+    pub show_tiles: bool,
+    /// Undo and redo stacks for edits and steps.
+    pub history: History,
+    /// Recent generations for rewinding.
+    pub timeline: Timeline,
+    pub seed: Option<u64>,
     pub recording: bool,
     pub recording_capped: bool,
     pub recorded_frames: Vec<Vec<u16>>,
-    pub last_export_msg: Option<String>,
+    pub recording_region: Region,
+    pub status_msg: Option<String>,
+    pub prompt: Option<Prompt>,
 }
 
 impl App {
     pub fn new(width: usize, height: usize, view_width: usize, view_height: usize) -> Self {
+        Self::with_universe(Box::new(Grid::new(width, height)), view_width, view_height)
+    }
+
+    pub fn with_universe(
+        universe: Box<dyn Universe>,
+        view_width: usize,
+        view_height: usize,
+    ) -> Self {
+        let grid_size = universe
+            .bounds()
+            .map_or((DEFAULT_GRID_SIZE, DEFAULT_GRID_SIZE), |b| {
+                (b.width, b.height)
+            });
         Self {
-            grid: Grid::new(width, height),
+            universe,
             paused: true,
             generation: 0,
             cursor_x: 0,
             cursor_y: 0,
             tick_rate: Duration::from_millis(100),
             cursor_visible: false,
-            pattern_mode: false,
+            last_pattern: 0,
+            library: Library::new(patterns::ALL.iter().map(|&p| p.clone()).collect()),
+            library_open: false,
+            patterns: patterns::ALL.iter().map(|&p| p.clone()).collect(),
+            placing: None,
+            transform: Transform::default(),
+            drag: None,
+            selection_anchor: None,
+            clipboard: None,
+            show_minimap: false,
             view_width,
             view_height,
             viewport_x: 0,
             viewport_y: 0,
             zoom: 1,
+            density: DEFAULT_DENSITY,
+            soup_area: SoupArea::Grid,
+            soup_size: DEFAULT_SOUP_SIZE,
+            grid_size,
+            step_exp: 0,
+            show_ages: true,
+            threads: grid::default_threads(),
+            show_tiles: false,
+            history: History::new(history::DEFAULT_MAX_CELLS),
+            timeline: Timeline::new(timeline::DEFAULT_FRAMES),
+            seed: None,
             recording: false,
             recording_capped: false,
             recorded_frames: Vec::new(),
-            last_export_msg: None,
+            recording_region: Region {
+                x: 0,
+                y: 0,
+                width: 0,
+                height: 0,
+            },
+            status_msg: None,
+            prompt: None,
         }
     }
 
+    /// Advances by the current step size, 2^`step_exp` generations.
     pub fn step(&mut self) {
-        self.grid.step();
-        self.generation += 1;
+        self.advance(1 << self.step_exp);
+    }
+
+    pub fn advance(&mut self, generations: u64) {
+        self.history
+            .record_step(&*self.universe, self.generation, !self.paused);
+        // HashLife jumps reach patterns far too big to list every frame
+        let timed = generations == 1 || self.universe.engine() != Engine::HashLife;
+        // Resuming from a rewound or edited generation replaces what followed it
+        if timed && self.timeline.newest_generation() != Some(self.generation) {
+            self.timeline.record(&*self.universe, self.generation);
+        }
+        self.universe.step_by(generations);
+        self.generation += generations as usize;
+        if timed {
+            self.timeline.record(&*self.universe, self.generation);
+        } else {
+            self.timeline.clear();
+        }
         if self.recording {
-            self.recorded_frames.push(self.grid.cells.clone());
-            if self.recorded_frames.len() >= MAX_RECORDING_FRAMES {
+            self.recorded_frames.push(self.recording_frame());
+            if self.recorded_frames.len() >= self.recording_limit() {
                 self.recording = false;
                 self.recording_capped = true;
             }
         }
     }
 
+    /// Starts recording the whole grid, or the area on screen of a large grid
+    /// or an infinite plane.
     pub fn start_recording(&mut self) {
+        self.recording_region = match self.universe.bounds() {
+            Some(bounds) if bounds.width * bounds.height <= MAX_RECORDING_AREA => bounds,
+            _ => self.view_region(),
+        };
         self.recorded_frames.clear();
-        self.recorded_frames.push(self.grid.cells.clone());
+        let frame = self.recording_frame();
+        self.recorded_frames.push(frame);
         self.recording = true;
         self.recording_capped = false;
     }
 
+    /// Frames recorded before the recording is saved.
+    pub fn recording_limit(&self) -> usize {
+        let area = self.recording_region.width * self.recording_region.height;
+        MAX_RECORDING_FRAMES.min(MAX_RECORDED_CELLS / area.max(1))
+    }
+
+    /// Ages of the cells in the recording region, following the edges of a
+    /// wrapping grid where the view runs past them.
+    fn recording_frame(&self) -> Vec<u16> {
+        let region = self.recording_region;
+        let inside = match self.universe.bounds() {
+            Some(bounds) => region.intersect(&bounds) == Some(region),
+            None => true,
+        };
+        if inside {
+            return self.universe.snapshot(region);
+        }
+        let mut cells = Vec::with_capacity(region.width * region.height);
+        for y in region.y..region.y + region.height as isize {
+            for x in region.x..region.x + region.width as isize {
+                let cell = self.universe.wrap(x, y);
+                cells.push(cell.map_or(0, |(x, y)| self.universe.get(x, y)));
+            }
+        }
+        cells
+    }
+
     pub fn stop_recording(&mut self) -> Vec<Vec<u16>> {
         self.recording = false;
         std::mem::take(&mut self.recorded_frames)
@@ -73,73 +294,1345 @@ impl App {
 
     pub fn toggle_pause(&mut self) {
         self.paused = !self.paused;
+        self.history.end_run();
+    }
+
+    /// Cell columns (at zoom 1) and rows that fit in a terminal of the given
+    /// size, inside the grid border and above the status bar.
+    pub fn view_size(cols: u16, rows: u16) -> (usize, usize) {
+        (
+            (cols as usize).saturating_sub(2) / 2,
+            (rows as usize).saturating_sub(3),
+        )
+    }
+
+    /// Adapts the view to a new terminal size.
+    pub fn resize(&mut self, cols: u16, rows: u16) {
+        (self.view_width, self.view_height) = Self::view_size(cols, rows);
+        self.clamp_viewport();
+    }
+
+    /// Number of grid columns and rows that fit in the view at the current zoom.
+    pub fn visible_size(&self) -> (usize, usize) {
+        let view_width = if self.library_open {
+            self.view_width.saturating_sub(LIBRARY_COLUMNS / 2)
+        } else {
+            self.view_width
+        };
+        if self.zoom == -1 {
+            (view_width * 4, self.view_height * 4)
+        } else if self.zoom == 0 {
+            (view_width * 2, self.view_height * 2)
+        } else {
+            let zoom = self.zoom as usize;
+            (view_width / zoom, self.view_height / zoom)
+        }
+    }
+
+    /// Cells currently shown on screen, clipped to the edges of a finite grid.
+    /// On a wrapping grid the view may lie past the edges; see `view_areas`.
+    fn visible_region(&self) -> Region {
+        let (cols, rows) = self.visible_size();
+        let view = Region {
+            x: self.viewport_x,
+            y: self.viewport_y,
+            width: cols,
+            height: rows,
+        };
+        match self.universe.bounds() {
+            Some(bounds) => view.intersect(&bounds).unwrap_or(Region {
+                width: 0,
+                height: 0,
+                ..view
+            }),
+            None => view,
+        }
+    }
+
+    /// Moves the viewport by a quarter of the visible area in each direction,
+    /// keeping it within the edges of a finite grid.
+    pub fn pan(&mut self, dx: isize, dy: isize) {
+        let (cols, rows) = self.visible_size();
+        self.viewport_x += dx * (cols / 4).max(1) as isize;
+        self.viewport_y += dy * (rows / 4).max(1) as isize;
+        self.clamp_viewport();
+    }
+
+    /// Whether the edges of a finite grid join, so the view can scroll across them.
+    pub fn wraps(&self) -> bool {
+        self.universe
+            .topology()
+            .is_some_and(|t| t != Topology::Plane)
+    }
+
+    /// Keeps the viewport within the edges of a finite grid. A view smaller
+    /// than a wrapping grid scrolls freely, kept within two laps of the origin,
+    /// after which even twisted edges line up again.
+    pub fn clamp_viewport(&mut self) {
+        if let Some(bounds) = self.universe.bounds() {
+            let (cols, rows) = self.visible_size();
+            let wraps = self.wraps();
+            let clamp = |start: isize, view: usize, extent: usize| {
+                if wraps && extent > view {
+                    start.rem_euclid(2 * extent as isize)
+                } else {
+                    start.clamp(0, (extent as isize - view as isize).max(0))
+                }
+            };
+            self.viewport_x = clamp(self.viewport_x, cols, bounds.width);
+            self.viewport_y = clamp(self.viewport_y, rows, bounds.height);
+        }
+    }
+
+    /// Positions drawn on screen. Unlike `visible_region`, these run past the
+    /// edges of a wrapping grid and are mapped onto it with `Universe::wrap`.
+    pub fn view_region(&self) -> Region {
+        if !self.wraps() {
+            return self.visible_region();
+        }
+        let (cols, rows) = self.visible_size();
+        let bounds = self.universe.bounds().unwrap();
+        let clip = |start: isize, view: usize, extent: usize| {
+            if extent > view {
+                (start, view)
+            } else {
+                (0, extent)
+            }
+        };
+        let (x, width) = clip(self.viewport_x, cols, bounds.width);
+        let (y, height) = clip(self.viewport_y, rows, bounds.height);
+        Region {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Parts of the grid on screen: up to four regions when the view runs
+    /// across the edges of a wrapping grid, each mapped back onto the grid.
+    pub fn view_areas(&self) -> Vec<Region> {
+        let view = self.view_region();
+        let Some(bounds) = self.universe.bounds().filter(|_| self.wraps()) else {
+            return vec![view];
+        };
+        // Split each axis where it crosses an edge, leaving pieces that lie
+        // within one copy of the grid
+        let split = |start: isize, len: usize, extent: usize| {
+            let end = start + len as isize;
+            let edge = (start.div_euclid(extent as isize) + 1) * extent as isize;
+            if end <= edge {
+                vec![(start, end)]
+            } else {
+                vec![(start, edge), (edge, end)]
+            }
+        };
+        let mut areas = Vec::new();
+        for &(x0, x1) in &split(view.x, view.width, bounds.width) {
+            for &(y0, y1) in &split(view.y, view.height, bounds.height) {
+                let corners = [(x0, y0), (x1 - 1, y1 - 1)]
+                    .into_iter()
+                    .filter_map(|(x, y)| self.universe.wrap(x, y));
+                areas.extend(Region::bounding(corners));
+            }
+        }
+        areas
+    }
+
+    /// Where the cursor appears on screen, which on a wrapping grid need not
+    /// be its own coordinates.
+    fn cursor_on_screen(&self) -> (isize, isize) {
+        let cursor = (self.cursor_x, self.cursor_y);
+        if self.wraps() {
+            let view = self.view_region();
+            for y in view.y..view.y + view.height as isize {
+                for x in view.x..view.x + view.width as isize {
+                    if self.universe.wrap(x, y) == Some(cursor) {
+                        return (x, y);
+                    }
+                }
+            }
+        }
+        cursor
+    }
+
+    /// Scrolls the least distance that brings screen position `(x, y)` into
+    /// view after moving by `(dx, dy)`, keeping a small margin ahead of it.
+    fn scroll_to(&mut self, x: isize, y: isize, dx: isize, dy: isize) {
+        let (cols, rows) = self.visible_size();
+        let scroll = |start: &mut isize, pos: isize, view: usize, toward: isize| {
+            let margin = (view / 8) as isize;
+            let before = if toward < 0 { margin } else { 0 };
+            let after = if toward > 0 { margin } else { 0 };
+            if pos < *start + before {
+                *start = pos - before;
+            } else if pos >= *start + view as isize - after {
+                *start = pos - view as isize + 1 + after;
+            }
+        };
+        scroll(&mut self.viewport_x, x, cols, dx);
+        scroll(&mut self.viewport_y, y, rows, dy);
+        self.clamp_viewport();
+    }
+
+    /// Centers the view on a position.
+    fn center_on(&mut self, x: isize, y: isize) {
+        let (cols, rows) = self.visible_size();
+        self.viewport_x = x - cols as isize / 2;
+        self.viewport_y = y - rows as isize / 2;
+        self.clamp_viewport();
+    }
+
+    pub fn center_on_cursor(&mut self) {
+        self.cursor_visible = true;
+        self.center_on(self.cursor_x, self.cursor_y);
+    }
+
+    /// Zooms in as far as keeps every live cell on screen and centers on them.
+    pub fn fit_all(&mut self) {
+        let Some(live) = self.universe.bounding_box() else {
+            self.status_msg = Some("No live cells".to_string());
+            return;
+        };
+        for zoom in (MIN_ZOOM..=MAX_ZOOM).rev() {
+            self.zoom = zoom;
+            let (cols, rows) = self.visible_size();
+            if live.width <= cols && live.height <= rows {
+                break;
+            }
+        }
+        self.center_on(
+            live.x + live.width as isize / 2,
+            live.y + live.height as isize / 2,
+        );
+        self.status_msg = Some(format!(
+            "Showing {}x{} of live cells at zoom {}",
+            live.width, live.height, self.zoom
+        ));
+    }
+
+    /// Moves the cursor to coordinates typed as `x,y` or `x y` and centers on it.
+    pub fn go_to(&mut self, input: &str) {
+        let coords: Vec<_> = input
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|s| !s.is_empty())
+            .map(str::parse::<isize>)
+            .collect();
+        let [Ok(x), Ok(y)] = coords[..] else {
+            self.status_msg = Some(format!("Expected x,y, got '{}'", input.trim()));
+            return;
+        };
+        match self.universe.wrap(x, y) {
+            Some((x, y)) => {
+                (self.cursor_x, self.cursor_y) = (x, y);
+                self.center_on_cursor();
+                self.status_msg = Some(format!("Cursor at ({},{})", x, y));
+            }
+            None => self.status_msg = Some(format!("({},{}) is outside the grid", x, y)),
+        }
+    }
+
+    /// Cell offset from the viewport origin of a terminal position inside or
+    /// outside the grid view, accounting for the border around it.
+    fn screen_offset(&self, column: u16, row: u16) -> (isize, isize) {
+        let (column, row) = (column as isize - 1, row as isize - 1);
+        if self.zoom == -1 {
+            (column * 2, row * 4)
+        } else if self.zoom == 0 {
+            (column, row * 2)
+        } else {
+            let zoom = self.zoom as isize;
+            (column.div_euclid(2 * zoom), row.div_euclid(zoom))
+        }
+    }
+
+    /// Cells across and down that share one character at the current zoom.
+    fn char_cells(&self) -> (isize, isize) {
+        match self.zoom {
+            -1 => (2, 4),
+            0 => (1, 2),
+            _ => (1, 1),
+        }
+    }
+
+    /// The cell drawn at a terminal position, if any. In half-block and
+    /// braille modes this is the top-left of the cells sharing a character.
+    pub fn cell_at(&self, column: u16, row: u16) -> Option<(isize, isize)> {
+        let (x, y) = self.screen_cell(column, row)?;
+        self.universe.wrap(x, y)
+    }
+
+    /// The position drawn at a terminal position before wrapping onto the grid.
+    fn screen_cell(&self, column: u16, row: u16) -> Option<(isize, isize)> {
+        let (dx, dy) = self.screen_offset(column, row);
+        let (cols, rows) = self.visible_size();
+        if dx < 0 || dy < 0 || dx >= cols as isize || dy >= rows as isize {
+            return None;
+        }
+        let (x, y) = (self.viewport_x + dx, self.viewport_y + dy);
+        self.view_region().contains(x, y).then_some((x, y))
+    }
+
+    /// Starts drawing (`age` 1) or erasing (`age` 0) at a terminal position.
+    pub fn start_paint(&mut self, column: u16, row: u16, age: u16) {
+        self.commit_frame();
+        self.drag = Some(Drag::Paint {
+            age,
+            last: None,
+            before: Vec::new(),
+            after: Vec::new(),
+        });
+        self.drag_to(column, row);
+    }
+
+    pub fn start_pan(&mut self, column: u16, row: u16) {
+        self.drag = Some(Drag::Pan { column, row });
+    }
+
+    /// Continues the current drag to a terminal position.
+    pub fn drag_to(&mut self, column: u16, row: u16) {
+        match self.drag.take() {
+            Some(Drag::Pan {
+                column: from_column,
+                row: from_row,
+            }) => {
+                let (x0, y0) = self.screen_offset(from_column, from_row);
+                let (x1, y1) = self.screen_offset(column, row);
+                let moved = (x0, y0) != (x1, y1);
+                if moved {
+                    self.viewport_x -= x1 - x0;
+                    self.viewport_y -= y1 - y0;
+                    self.clamp_viewport();
+                }
+                // Motion smaller than a cell builds up until it moves the view
+                self.drag = Some(if moved {
+                    Drag::Pan { column, row }
+                } else {
+                    Drag::Pan {
+                        column: from_column,
+                        row: from_row,
+                    }
+                });
+            }
+            Some(Drag::Paint {
+                age,
+                last,
+                mut before,
+                mut after,
+            }) => {
+                // Interpolate on screen so strokes across a wrapping edge stay
+                // short, a character at a time
+                let target = self.screen_cell(column, row);
+                if let Some((x1, y1)) = target {
+                    let (w, h) = self.char_cells();
+                    let view = self.view_region();
+                    // Fill the gaps between mouse events with a straight line
+                    let (x0, y0) = last.unwrap_or((x1, y1));
+                    let (cols, rows) = ((x1 - x0) / w, (y1 - y0) / h);
+                    let steps = cols.abs().max(rows.abs()).max(1);
+                    for i in 0..=steps {
+                        let t = i as f64 / steps as f64;
+                        let x = x0 + (cols as f64 * t).round() as isize * w;
+                        let y = y0 + (rows as f64 * t).round() as isize * h;
+                        // Every cell the character covers
+                        for (x, y) in (y..y + h).flat_map(|y| (x..x + w).map(move |x| (x, y))) {
+                            if !view.contains(x, y) {
+                                continue;
+                            }
+                            let Some((x, y)) = self.universe.wrap(x, y) else {
+                                continue;
+                            };
+                            let old = self.universe.get(x, y);
+                            if (old > 0) != (age > 0) {
+                                self.universe.set(x, y, age);
+                                before.push((x, y, old));
+                                after.push((x, y, age));
+                            }
+                        }
+                    }
+                }
+                self.drag = Some(Drag::Paint {
+                    age,
+                    last: target,
+                    before,
+                    after,
+                });
+            }
+            Some(Drag::Minimap) => {
+                self.jump_to_minimap(column, row);
+            }
+            None => {}
+        }
+    }
+
+    /// Finishes the current drag, recording a paint stroke as one edit.
+    pub fn end_drag(&mut self) {
+        if let Some(Drag::Paint { before, after, .. }) = self.drag.take() {
+            self.history.record_edit(&before, &after);
+        }
+    }
+
+    /// Zooms in (`delta` > 0) or out, keeping the cell under a terminal
+    /// position in place.
+    pub fn zoom_at(&mut self, column: u16, row: u16, delta: i32) {
+        let (dx, dy) = self.screen_offset(column, row);
+        let (x, y) = (self.viewport_x + dx, self.viewport_y + dy);
+        if delta > 0 {
+            self.zoom_in();
+        } else {
+            self.zoom_out();
+        }
+        let (dx, dy) = self.screen_offset(column, row);
+        self.viewport_x = x - dx;
+        self.viewport_y = y - dy;
+        self.clamp_viewport();
     }
 
     pub fn randomize(&mut self) {
-        self.grid.randomize();
-        self.generation = 0;
+        self.randomize_area(SoupArea::Grid);
+    }
+
+    /// Generates a soup in `area` from a fresh seed.
+    pub fn randomize_area(&mut self, area: SoupArea) {
+        self.soup(area, fastrand::u64(..));
+    }
+
+    /// Generates a soup in `area` from `seed`; the same seed, area and density
+    /// always produce the same cells.
+    pub fn soup(&mut self, area: SoupArea, seed: u64) {
+        self.commit_frame();
+        let before = self.universe.live_cells();
+        let regions = match area {
+            SoupArea::Grid => vec![self.universe.bounds().unwrap_or_else(|| self.view_region())],
+            SoupArea::Viewport => self.view_areas(),
+            SoupArea::Selection(region) => vec![region],
+            SoupArea::Centered(size) => {
+                self.universe.clear();
+                let (cx, cy, w, h) = match self.universe.bounds() {
+                    Some(b) => (
+                        b.width as isize / 2,
+                        b.height as isize / 2,
+                        size.min(b.width),
+                        size.min(b.height),
+                    ),
+                    None => (0, 0, size, size),
+                };
+                vec![Region {
+                    x: cx - w as isize / 2,
+                    y: cy - h as isize / 2,
+                    width: w,
+                    height: h,
+                }]
+            }
+        };
+        let mut rng = fastrand::Rng::with_seed(seed);
+        for region in regions {
+            self.universe.randomize(region, self.density, &mut rng);
+        }
+        self.history
+            .record_edit(&before, &self.universe.live_cells());
+        // A selection soup edits the running pattern; other soups start afresh
+        if !matches!(area, SoupArea::Selection(_)) {
+            self.generation = 0;
+        }
+        self.soup_area = area;
+        self.seed = Some(seed);
     }
 
     pub fn clear(&mut self) {
-        self.grid.clear();
+        self.commit_frame();
+        let before = self.universe.live_cells();
+        self.universe.clear();
+        self.history.record_edit(&before, &[]);
         self.generation = 0;
+        self.seed = None;
     }
 
     pub fn toggle_cell(&mut self) {
-        let idx = self.cursor_y * self.grid.width + self.cursor_x;
-        self.grid.cells[idx] = if self.grid.cells[idx] > 0 { 0 } else { 1 };
+        self.commit_frame();
+        let (x, y) = (self.cursor_x, self.cursor_y);
+        let age = self.universe.get(x, y);
+        let new = if age > 0 { 0 } else { 1 };
+        self.universe.set(x, y, new);
+        self.history.record_edit(&[(x, y, age)], &[(x, y, new)]);
+    }
+
+    /// Moves the cursor by one cell, following the grid topology across edges
+    /// and scrolling to keep it in view.
+    fn move_cursor(&mut self, dx: isize, dy: isize) {
+        let (sx, sy) = self.cursor_on_screen();
+        let target = self.universe.wrap(self.cursor_x + dx, self.cursor_y + dy);
+        if let Some((x, y)) = target {
+            self.cursor_x = x;
+            self.cursor_y = y;
+            self.scroll_to(sx + dx, sy + dy, dx, dy);
+        }
     }
 
     pub fn move_left(&mut self) {
-        self.cursor_x = (self.cursor_x as isize - 1).rem_euclid(self.grid.width as isize) as usize;
+        self.move_cursor(-1, 0);
     }
 
     pub fn move_down(&mut self) {
-        self.cursor_y = (self.cursor_y as isize + 1).rem_euclid(self.grid.height as isize) as usize;
+        self.move_cursor(0, 1);
     }
 
     pub fn move_up(&mut self) {
-        self.cursor_y = (self.cursor_y as isize - 1).rem_euclid(self.grid.height as isize) as usize;
+        self.move_cursor(0, -1);
     }
 
     pub fn move_right(&mut self) {
-        self.cursor_x = (self.cursor_x as isize + 1).rem_euclid(self.grid.width as isize) as usize;
+        self.move_cursor(1, 0);
     }
 
     pub fn speed_up(&mut self) {
         self.tick_rate = self
             .tick_rate
             .saturating_sub(Duration::from_millis(25))
-            .max(Duration::from_millis(10));
+            .max(Duration::from_millis(MIN_TICK_MS));
     }
 
     pub fn slow_down(&mut self) {
         self.tick_rate =
-            (self.tick_rate + Duration::from_millis(25)).min(Duration::from_millis(1000));
+            (self.tick_rate + Duration::from_millis(25)).min(Duration::from_millis(MAX_TICK_MS));
     }
 
+    /// Shows or hides the cursor, bringing it to the middle of the view if it
+    /// was off screen.
     pub fn toggle_cursor(&mut self) {
         self.cursor_visible = !self.cursor_visible;
+        let (x, y) = self.cursor_on_screen();
+        if self.cursor_visible && !self.view_region().contains(x, y) {
+            let view = self.view_region();
+            let center = (
+                view.x + view.width as isize / 2,
+                view.y + view.height as isize / 2,
+            );
+            if let Some((x, y)) = self.universe.wrap(center.0, center.1) {
+                (self.cursor_x, self.cursor_y) = (x, y);
+            }
+        }
+    }
+
+    pub fn toggle_minimap(&mut self) {
+        self.show_minimap = !self.show_minimap;
+    }
+
+    /// Starts dragging the view rectangle if a terminal position is on the
+    /// minimap, centering the view on the cells there.
+    pub fn start_minimap_drag(&mut self, column: u16, row: u16) -> bool {
+        let on_map = self.show_minimap
+            && Minimap::new(self).is_some_and(|map| map.char_at(column, row).is_some());
+        if on_map {
+            self.drag = Some(Drag::Minimap);
+            self.jump_to_minimap(column, row);
+        }
+        on_map
+    }
+
+    /// Centers the view on the minimap character nearest a terminal position.
+    fn jump_to_minimap(&mut self, column: u16, row: u16) {
+        if let Some(map) = Minimap::new(self) {
+            let (col, row) = map.nearest_char(column, row);
+            let (x, y) = map.cell(col, row);
+            self.center_on(x, y);
+        }
+    }
+
+    /// Moves the view rectangle by one minimap character in each direction.
+    pub fn nudge_view(&mut self, dx: isize, dy: isize) {
+        if let Some(map) = Minimap::new(self) {
+            self.viewport_x += dx * (map.scale * 2) as isize;
+            self.viewport_y += dy * (map.scale * 4) as isize;
+            self.clamp_viewport();
+        }
     }
 
     pub fn zoom_in(&mut self) {
-        if self.zoom < 8 {
+        if self.zoom < MAX_ZOOM {
             self.zoom += 1;
         }
     }
 
     pub fn zoom_out(&mut self) {
-        if self.zoom > 0 {
+        if self.zoom > MIN_ZOOM {
             self.zoom -= 1;
         }
     }
 
-    pub fn place_pattern(&mut self, patterns: &crate::patterns::Pattern) {
-        for &(dx, dy) in patterns.cells {
-            let x = (self.cursor_x as isize + dx).rem_euclid(self.grid.width as isize) as usize;
-            let y = (self.cursor_y as isize + dy).rem_euclid(self.grid.height as isize) as usize;
-            let idx = y * self.grid.width + x;
-            self.grid.cells[idx] = 1;
+    /// Live cells of the whole universe as a pattern tagged with the current rule.
+    pub fn grid_pattern(&self) -> Pattern {
+        let cells = self
+            .universe
+            .live_cells()
+            .into_iter()
+            .map(|(x, y, _)| (x, y))
+            .collect();
+        let mut pattern = Pattern::from_cells(String::new(), cells);
+        pattern.rule = Some(self.universe.rule());
+        pattern
+    }
+
+    pub fn place_pattern(&mut self, pattern: &Pattern) {
+        self.commit_frame();
+        let mut before = Vec::new();
+        let mut after = Vec::new();
+        for &(dx, dy) in pattern.cells.iter() {
+            let target = self.universe.wrap(self.cursor_x + dx, self.cursor_y + dy);
+            if let Some((x, y)) = target {
+                let age = self.universe.get(x, y);
+                if age != 1 {
+                    before.push((x, y, age));
+                    after.push((x, y, 1));
+                    self.universe.set(x, y, 1);
+                }
+            }
+        }
+        self.history.record_edit(&before, &after);
+    }
+
+    /// Starts a selection anchored at the cursor, or ends the current one.
+    pub fn toggle_selection(&mut self) {
+        if self.selection_anchor.take().is_none() {
+            self.selection_anchor = Some((self.cursor_x, self.cursor_y));
+            self.cursor_visible = true;
+        }
+    }
+
+    /// Rectangle between the selection anchor and the cursor, inclusive.
+    pub fn selection(&self) -> Option<Region> {
+        let anchor = self.selection_anchor?;
+        Region::bounding([anchor, (self.cursor_x, self.cursor_y)])
+    }
+
+    /// Sets every cell in the selection to `cell(age)`, recording the change.
+    fn edit_selection(&mut self, mut cell: impl FnMut(u16) -> u16) -> Option<Region> {
+        let region = self.selection()?;
+        self.commit_frame();
+        let mut before = Vec::new();
+        let mut after = Vec::new();
+        for y in region.y..region.y + region.height as isize {
+            for x in region.x..region.x + region.width as isize {
+                let age = self.universe.get(x, y);
+                let new = cell(age);
+                if new != age {
+                    before.push((x, y, age));
+                    after.push((x, y, new));
+                    self.universe.set(x, y, new);
+                }
+            }
+        }
+        self.history.record_edit(&before, &after);
+        Some(region)
+    }
+
+    fn selection_message(&mut self, action: &str, region: Option<Region>) {
+        self.status_msg = Some(match region {
+            Some(r) => format!("{} {}x{} selection", action, r.width, r.height),
+            None => "Press v to start a selection".to_string(),
+        });
+    }
+
+    /// Live cells of `region` as a pattern tagged with the current rule.
+    fn region_pattern(&self, name: &str, region: Region) -> Pattern {
+        let mut cells = Vec::new();
+        for y in region.y..region.y + region.height as isize {
+            for x in region.x..region.x + region.width as isize {
+                if self.universe.get(x, y) > 0 {
+                    cells.push((x, y));
+                }
+            }
+        }
+        let mut pattern = Pattern::from_cells(name.to_string(), cells);
+        pattern.rule = Some(self.universe.rule());
+        pattern
+    }
+
+    /// Copies the live cells of the selection to the clipboard, returning the
+    /// selection and the number of cells copied.
+    fn copy(&mut self) -> Option<(Region, usize)> {
+        let region = self.selection()?;
+        let pattern = self.region_pattern("clipboard", region);
+        let count = pattern.cells.len();
+        self.clipboard = Some(pattern);
+        Some((region, count))
+    }
+
+    pub fn copy_selection(&mut self) {
+        self.status_msg = Some(match self.copy() {
+            Some((r, count)) => format!("Copied {} cells from {}x{}", count, r.width, r.height),
+            None => "Press v to start a selection".to_string(),
+        });
+    }
+
+    pub fn cut_selection(&mut self) {
+        let Some((r, count)) = self.copy() else {
+            self.selection_message("Cut", None);
+            return;
+        };
+        self.edit_selection(|_| 0);
+        self.selection_anchor = None;
+        self.status_msg = Some(format!("Cut {} cells from {}x{}", count, r.width, r.height));
+    }
+
+    /// Picks up the clipboard for placing at the cursor.
+    pub fn paste(&mut self) {
+        match self.clipboard.clone() {
+            Some(pattern) => self.start_placing(pattern),
+            None => self.status_msg = Some("Clipboard is empty".to_string()),
+        }
+    }
+
+    /// Picks up pattern `index` of the pattern list.
+    pub fn pick_pattern(&mut self, index: usize) {
+        if let Some(pattern) = self.patterns.get(index) {
+            self.last_pattern = index;
+            self.start_placing(pattern.clone());
+        }
+    }
+
+    pub fn open_library(&mut self) {
+        self.library_open = true;
+        self.status_msg = (self.library.errors > 0)
+            .then(|| format!("{} pattern files could not be read", self.library.errors));
+    }
+
+    pub fn close_library(&mut self) {
+        self.library_open = false;
+        self.clamp_viewport();
+    }
+
+    /// Closes the library and picks up the highlighted pattern.
+    pub fn pick_from_library(&mut self) {
+        let Some(entry) = self.library.selected_entry() else {
+            return;
+        };
+        let pattern = entry.pattern.clone();
+        self.close_library();
+        self.start_placing(pattern);
+    }
+
+    /// Shows `pattern` as a ghost at the cursor until it is stamped.
+    pub fn start_placing(&mut self, pattern: Pattern) {
+        self.placing = Some(pattern);
+        self.cursor_visible = true;
+    }
+
+    pub fn stop_placing(&mut self) {
+        self.placing = None;
+    }
+
+    /// The pattern being placed, in its current orientation.
+    pub fn ghost(&self) -> Option<Pattern> {
+        Some(self.placing.as_ref()?.transformed(self.transform))
+    }
+
+    /// Cells the ghost covers, wrapped the way stamping it would.
+    pub fn ghost_cells(&self) -> Vec<(isize, isize)> {
+        let Some(ghost) = self.ghost() else {
+            return Vec::new();
+        };
+        ghost
+            .cells
+            .iter()
+            .filter_map(|&(dx, dy)| self.universe.wrap(self.cursor_x + dx, self.cursor_y + dy))
+            .collect()
+    }
+
+    /// Stamps the ghost at the cursor.
+    pub fn stamp(&mut self) {
+        if let Some(ghost) = self.ghost() {
+            let overlap = self.ghost_overlap();
+            self.place_pattern(&ghost);
+            let mut msg = format!("Placed {} ({})", ghost.name, self.transform);
+            if overlap > 0 {
+                msg.push_str(&format!(" over {} live cells", overlap));
+            }
+            self.status_msg = Some(msg);
+            self.stop_placing();
+        }
+    }
+
+    /// Moves the cursor to the cell at a terminal position, if there is one.
+    pub fn point_at(&mut self, column: u16, row: u16) -> bool {
+        let cell = self.cell_at(column, row);
+        if let Some((x, y)) = cell {
+            (self.cursor_x, self.cursor_y) = (x, y);
+        }
+        cell.is_some()
+    }
+
+    /// Stamps the ghost at the cell at a terminal position.
+    pub fn stamp_at(&mut self, column: u16, row: u16) {
+        if self.point_at(column, row) {
+            self.stamp();
         }
     }
+
+    /// Live cells the ghost would stamp over.
+    pub fn ghost_overlap(&self) -> usize {
+        self.ghost_cells()
+            .into_iter()
+            .filter(|&(x, y)| self.universe.get(x, y) > 0)
+            .count()
+    }
+
+    /// Changes the orientation patterns are placed in.
+    pub fn reorient(&mut self, change: fn(Transform) -> Transform) {
+        self.transform = change(self.transform);
+        self.status_msg = Some(format!("Orientation: {}", self.transform));
+    }
+
+    pub fn clear_selection(&mut self) {
+        let region = self.edit_selection(|_| 0);
+        self.selection_message("Cleared", region);
+    }
+
+    pub fn fill_selection(&mut self) {
+        let region = self.edit_selection(|age| age.max(1));
+        self.selection_message("Filled", region);
+    }
+
+    pub fn invert_selection(&mut self) {
+        let region = self.edit_selection(|age| if age > 0 { 0 } else { 1 });
+        self.selection_message("Inverted", region);
+    }
+
+    /// Fills the selection with a soup that `#` can regenerate from its seed.
+    pub fn randomize_selection(&mut self) {
+        let region = self.selection();
+        if let Some(region) = region {
+            self.randomize_area(SoupArea::Selection(region));
+        }
+        self.selection_message("Randomized", region);
+    }
+
+    pub fn cycle_topology(&mut self) {
+        let Some(topology) = self.universe.topology() else {
+            self.status_msg = Some("An infinite plane has no edges".to_string());
+            return;
+        };
+        self.universe.set_topology(topology.next());
+        self.status_msg = Some(format!("Topology: {}", topology.next()));
+    }
+
+    fn max_step_exp(&self) -> u32 {
+        match self.universe.engine() {
+            Engine::HashLife => MAX_HASHLIFE_STEP_EXP,
+            _ => MAX_STEP_EXP,
+        }
+    }
+
+    pub fn double_step(&mut self) {
+        self.step_exp = (self.step_exp + 1).min(self.max_step_exp());
+        self.status_msg = Some(format!("Step: 2^{} generations", self.step_exp));
+    }
+
+    pub fn halve_step(&mut self) {
+        self.step_exp = self.step_exp.saturating_sub(1);
+        self.status_msg = Some(format!("Step: 2^{} generations", self.step_exp));
+    }
+
+    /// Moves the cells, rule and (where supported) ages to a different engine.
+    /// Cells outside a finite grid are lost.
+    pub fn set_engine(&mut self, engine: Engine) {
+        let rule = self.universe.rule();
+        if let Some(b) = self.universe.bounds() {
+            self.grid_size = (b.width, b.height);
+        }
+        let mut next = engine.create(self.grid_size);
+        next.set_track_ages(self.show_ages);
+        next.set_threads(self.threads);
+        if let Err(e) = next.set_rule(rule) {
+            self.status_msg = Some(e);
+            return;
+        }
+
+        let mut lost = 0;
+        for (x, y, age) in self.universe.live_cells() {
+            match next.wrap(x, y) {
+                Some((x, y)) if next.get(x, y) == 0 => next.set(x, y, age),
+                _ => lost += 1,
+            }
+        }
+        if self.recording {
+            self.stop_recording();
+        }
+        self.universe = next;
+        self.history.clear();
+        self.timeline.clear();
+        self.step_exp = self.step_exp.min(self.max_step_exp());
+        if let Some((x, y)) = self.universe.wrap(self.cursor_x, self.cursor_y) {
+            (self.cursor_x, self.cursor_y) = (x, y);
+        } else {
+            (self.cursor_x, self.cursor_y) = (0, 0);
+        }
+        self.clamp_viewport();
+
+        let mut msg = format!("Engine: {}", self.universe.shape());
+        if lost > 0 {
+            msg.push_str(&format!(" ({} cells outside the grid dropped)", lost));
+        }
+        self.status_msg = Some(msg);
+    }
+
+    pub fn undo(&mut self) {
+        let undone = self
+            .history
+            .undo(&mut *self.universe, &mut self.generation)
+            .map(str::to_string);
+        self.status_msg = Some(match undone {
+            Some(change) => format!("Undid {} (gen {})", change, self.generation),
+            None => "Nothing to undo".to_string(),
+        });
+    }
+
+    pub fn redo(&mut self) {
+        let redone = self
+            .history
+            .redo(&mut *self.universe, &mut self.generation)
+            .map(str::to_string);
+        self.status_msg = Some(match redone {
+            Some(change) => format!("Redid {} (gen {})", change, self.generation),
+            None => "Nothing to redo".to_string(),
+        });
+    }
+
+    /// Moves `frames` frames along the timeline, pausing the simulation. The
+    /// first move is undoable, so `u` returns to where rewinding started.
+    pub fn scrub(&mut self, frames: isize) {
+        if self.timeline.is_empty() {
+            self.status_msg = Some("No generations recorded yet".to_string());
+            return;
+        }
+        let last = self.timeline.len() - 1;
+        let current = self.timeline.position().unwrap_or(last);
+        let target = current.saturating_add_signed(frames).min(last);
+        if target == current {
+            self.status_msg = Some(if frames < 0 {
+                "Oldest recorded generation".to_string()
+            } else {
+                "Newest recorded generation".to_string()
+            });
+            return;
+        }
+        if self.timeline.position().is_none() {
+            self.history
+                .record_step(&*self.universe, self.generation, false);
+        }
+        self.paused = true;
+        self.universe.clear();
+        for (x, y, age) in self.timeline.seek(target) {
+            self.universe.set(x, y, age);
+        }
+        self.generation = self.timeline.generation(target);
+        self.status_msg = None;
+    }
+
+    /// Makes the frame on screen the live state ahead of an edit, cutting off
+    /// the frames after it so scrubbing cannot discard the edit.
+    fn commit_frame(&mut self) {
+        if self.timeline.position().is_some() {
+            self.timeline.record(&*self.universe, self.generation);
+        }
+    }
+
+    /// Scrubs a tenth of the timeline at a time.
+    pub fn scrub_far(&mut self, direction: isize) {
+        self.scrub(direction * (self.timeline.len() / 10).max(1) as isize);
+    }
+
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads;
+        self.universe.set_threads(threads);
+    }
+
+    pub fn toggle_tiles(&mut self) {
+        self.show_tiles = !self.show_tiles;
+        self.status_msg = Some(if self.show_tiles {
+            "Active tile overlay on".to_string()
+        } else {
+            "Active tile overlay off".to_string()
+        });
+    }
+
+    pub fn toggle_ages(&mut self) {
+        self.show_ages = !self.show_ages;
+        self.universe.set_track_ages(self.show_ages);
+        self.status_msg = Some(if self.show_ages {
+            "Age colors on".to_string()
+        } else {
+            "Age colors off".to_string()
+        });
+    }
+
+    pub fn cycle_engine(&mut self) {
+        self.set_engine(self.universe.engine().next());
+    }
+
+    pub fn set_rule(&mut self, rule: Rule) {
+        if let Err(e) = self.universe.set_rule(rule) {
+            self.status_msg = Some(e);
+            return;
+        }
+        self.status_msg = Some(match rule.name() {
+            Some(name) => format!("Rule {} ({})", rule, name),
+            None => format!("Rule {}", rule),
+        });
+    }
+
+    pub fn open_prompt(&mut self, kind: PromptKind) {
+        let input = match kind {
+            PromptKind::Rule => self.universe.rule().to_string(),
+            PromptKind::OpenPattern => String::new(),
+            PromptKind::SavePattern => export::timestamped_filename("rle"),
+            PromptKind::SaveSession => export::timestamped_filename("petri"),
+            PromptKind::LoadSession => String::new(),
+            PromptKind::Seed => self.seed.map(|s| s.to_string()).unwrap_or_default(),
+            PromptKind::GoTo => format!("{},{}", self.cursor_x, self.cursor_y),
+        };
+        self.prompt = Some(Prompt { kind, input });
+    }
+
+    pub fn prompt_push(&mut self, c: char) {
+        if let Some(prompt) = &mut self.prompt {
+            prompt.input.push(c);
+        }
+    }
+
+    pub fn prompt_backspace(&mut self) {
+        if let Some(prompt) = &mut self.prompt {
+            prompt.input.pop();
+        }
+    }
+
+    pub fn cancel_prompt(&mut self) {
+        self.prompt = None;
+    }
+
+    pub fn submit_prompt(&mut self) {
+        let Some(prompt) = self.prompt.take() else {
+            return;
+        };
+        match prompt.kind {
+            PromptKind::Rule => match prompt.input.parse::<Rule>() {
+                Ok(rule) => self.set_rule(rule),
+                Err(e) => self.status_msg = Some(format!("Invalid rule: {}", e)),
+            },
+            PromptKind::OpenPattern => self.load_pattern(Path::new(prompt.input.trim())),
+            PromptKind::SavePattern => self.save_pattern(Path::new(prompt.input.trim())),
+            PromptKind::SaveSession => self.save_session(Path::new(prompt.input.trim())),
+            PromptKind::LoadSession => self.load_session(Path::new(prompt.input.trim())),
+            PromptKind::Seed => match prompt.input.trim().parse() {
+                Ok(seed) => self.soup(self.soup_area, seed),
+                Err(_) => self.status_msg = Some(format!("Invalid seed: {}", prompt.input)),
+            },
+            PromptKind::GoTo => self.go_to(&prompt.input),
+        }
+    }
+
+    pub fn save_session(&mut self, path: &Path) {
+        self.status_msg = Some(match session::save(self, path) {
+            Ok(()) => format!("Saved session {}", path.display()),
+            Err(e) => format!("Save failed: {}", e),
+        });
+    }
+
+    pub fn load_session(&mut self, path: &Path) {
+        self.status_msg = Some(match session::load(path) {
+            Ok(session) => {
+                session.restore(self);
+                format!("Loaded session {}", path.display())
+            }
+            Err(e) => format!("Load failed: {}", e),
+        });
+    }
+
+    /// Writes the selection, or the whole grid without one, as a pattern file.
+    pub fn save_pattern(&mut self, path: &Path) {
+        let (pattern, what) = match self.selection() {
+            Some(region) => (self.region_pattern("", region), "selection"),
+            None => (self.grid_pattern(), "grid"),
+        };
+        self.status_msg = Some(match export::write_pattern(&pattern, path) {
+            Ok(format) => format!(
+                "Saved {} {} as {} ({} cells)",
+                what,
+                path.display(),
+                format.name(),
+                pattern.cells.len()
+            ),
+            Err(e) => format!("Export failed: {}", e),
+        });
+    }
+
+    /// Loads a pattern file into the pattern list and picks it up for placing,
+    /// switching to the pattern's rule if it declares one.
+    pub fn load_pattern(&mut self, path: &Path) {
+        let pattern = match patterns::load(path) {
+            Ok(pattern) => pattern,
+            Err(e) => {
+                self.status_msg = Some(format!("Load failed: {}", e));
+                return;
+            }
+        };
+        self.last_pattern = self.patterns.len();
+        self.start_placing(pattern.clone());
+        let mut msg = format!(
+            "Loaded {} ({} cells) as pattern {}",
+            pattern.name,
+            pattern.cells.len(),
+            self.patterns.len() + 1
+        );
+        if let Some(rule) = pattern.rule.filter(|&r| r != self.universe.rule()) {
+            match self.universe.set_rule(rule) {
+                Ok(()) => msg.push_str(&format!(", rule {}", rule)),
+                Err(e) => msg.push_str(&format!(", {}", e)),
+            }
+        }
+        self.patterns.push(pattern);
+        self.status_msg = Some(msg);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_terminal_positions_to_cells() {
+        let mut app = App::new(64, 64, 20, 10);
+        app.viewport_x = 5;
+        app.viewport_y = 7;
+        // Half blocks: one column and two rows of cells per character
+        app.zoom = 0;
+        assert_eq!(app.cell_at(1, 1), Some((5, 7)));
+        assert_eq!(app.cell_at(4, 3), Some((8, 11)));
+        assert_eq!(app.cell_at(0, 1), None);
+        assert_eq!(app.cell_at(41, 1), None);
+        // Zoom 2: four columns and two rows per cell
+        app.zoom = 2;
+        assert_eq!(app.cell_at(1, 1), Some((5, 7)));
+        assert_eq!(app.cell_at(8, 4), Some((6, 8)));
+        assert_eq!(app.cell_at(9, 5), Some((7, 9)));
+
+        // Cells past the edge of a small grid are not shown
+        let app = App::new(4, 4, 20, 10);
+        assert_eq!(app.cell_at(8, 1), Some((3, 0)));
+        assert_eq!(app.cell_at(9, 1), None);
+    }
+
+    #[test]
+    fn paint_strokes_fill_gaps_and_undo_at_once() {
+        let mut app = App::new(64, 64, 20, 10);
+        app.start_paint(1, 1, 1);
+        app.drag_to(9, 1);
+        app.end_drag();
+        let mut row: Vec<_> = app.universe.live_cells();
+        row.sort();
+        assert_eq!(row, (0..5).map(|x| (x, 0, 1)).collect::<Vec<_>>());
+
+        app.start_paint(3, 1, 0);
+        app.end_drag();
+        assert_eq!(app.universe.population(), 4);
+        app.undo();
+        assert_eq!(app.universe.population(), 5);
+        app.undo();
+        assert_eq!(app.universe.population(), 0);
+    }
+
+    #[test]
+    fn painting_when_zoomed_out_covers_whole_characters() {
+        let mut app = App::new(64, 64, 20, 10);
+        app.zoom = 0;
+        app.start_paint(1, 1, 1);
+        app.end_drag();
+        assert_eq!(app.universe.population(), 2);
+        app.zoom = MIN_ZOOM;
+        app.start_paint(2, 2, 1);
+        app.drag_to(4, 2);
+        app.end_drag();
+        // Three characters of 2×4 cells
+        assert_eq!(app.universe.population(), 2 + 24);
+        assert_eq!(app.universe.get(7, 7), 1);
+        assert_eq!(app.universe.get(8, 7), 0);
+        app.start_paint(2, 2, 0);
+        app.end_drag();
+        assert_eq!(app.universe.population(), 2 + 16);
+    }
+
+    #[test]
+    fn zooming_keeps_the_cell_under_the_pointer() {
+        let mut app = App::new(256, 256, 20, 10);
+        app.viewport_x = 100;
+        app.viewport_y = 100;
+        let cell = app.cell_at(11, 5);
+        app.zoom_at(11, 5, 1);
+        assert_eq!(app.zoom, 2);
+        assert_eq!(app.cell_at(11, 5), cell);
+        app.zoom_at(11, 5, -1);
+        app.zoom_at(11, 5, -1);
+        assert_eq!(app.zoom, 0);
+        assert_eq!(app.cell_at(11, 5), cell);
+        app.zoom_at(11, 5, -1);
+        app.zoom_at(11, 5, -1);
+        assert_eq!(app.zoom, MIN_ZOOM);
+        assert_eq!(app.visible_size(), (80, 40));
+        assert_eq!(app.cell_at(11, 5), cell);
+    }
+
+    #[test]
+    fn view_scrolls_across_wrapping_edges() {
+        let mut app = App::new(64, 64, 10, 10);
+        app.cursor_visible = true;
+        for _ in 0..3 {
+            app.move_left();
+        }
+        assert_eq!((app.cursor_x, app.cursor_y), (61, 0));
+        // The view follows the cursor past the left edge, one cell of margin
+        // to its left, instead of jumping to the far side of the grid
+        assert_eq!(app.viewport_x, (-3 - 1_isize).rem_euclid(128));
+        assert_eq!(app.cursor_on_screen(), (125, 0));
+        assert_eq!(app.cell_at(3, 1), Some((61, 0)));
+        assert_eq!(app.cell_at(9, 1), Some((0, 0)));
+
+        // A soup over the view fills the cells on both sides of the edge
+        app.density = 1.0;
+        app.soup(SoupArea::Viewport, 1);
+        assert_eq!(app.universe.population(), 100);
+        assert!(app.universe.get(61, 9) > 0 && app.universe.get(5, 0) > 0);
+        app.universe.set_topology(Topology::KleinBottle);
+        app.viewport_y = 60;
+        app.clear();
+        app.soup(SoupArea::Viewport, 1);
+        assert_eq!(app.universe.population(), 100);
+
+        app.universe.set_topology(Topology::Plane);
+        app.center_on_cursor();
+        assert_eq!(app.visible_region(), app.view_region());
+        assert!(app.view_region().contains(61, 0));
+    }
+
+    #[test]
+    fn selection_soups_replay_from_their_seed_and_save() {
+        let mut app = App::new(64, 64, 40, 20);
+        app.universe.set(0, 0, 1);
+        app.generation = 7;
+        (app.cursor_x, app.cursor_y) = (10, 10);
+        app.toggle_selection();
+        (app.cursor_x, app.cursor_y) = (19, 14);
+        app.randomize_selection();
+        let region = app.selection().unwrap();
+        assert_eq!(app.soup_area, SoupArea::Selection(region));
+        let cells = app.universe.live_cells();
+        assert!(cells.len() > 1);
+        assert!(
+            cells
+                .iter()
+                .all(|&(x, y, _)| (x, y) == (0, 0) || region.contains(x, y))
+        );
+
+        // The soup is an edit of the running pattern, undone on its own
+        assert_eq!(app.generation, 7);
+        app.undo();
+        assert_eq!(app.universe.live_cells(), vec![(0, 0, 1)]);
+        assert_eq!(app.generation, 7);
+
+        app.soup(app.soup_area, app.seed.unwrap());
+        assert_eq!(app.universe.live_cells(), cells);
+
+        let path = std::env::temp_dir().join(format!("petri-selection-{}.rle", std::process::id()));
+        app.save_pattern(&path);
+        let saved = patterns::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(saved.cells.len(), cells.len() - 1);
+    }
+
+    #[test]
+    fn hashlife_jumps_skip_the_timeline() {
+        let mut app = App::with_universe(Engine::HashLife.create((0, 0)), 40, 20);
+        app.place_pattern(&patterns::GLIDER_GUN);
+        app.advance(1);
+        assert_eq!(app.timeline.len(), 2);
+        app.advance(1 << 24);
+        assert!(app.timeline.is_empty());
+        assert_eq!(app.generation, 1 + (1 << 24));
+    }
+
+    #[test]
+    fn large_grids_record_the_area_on_screen() {
+        let mut app = App::new(64, 64, 20, 10);
+        app.start_recording();
+        assert_eq!(app.recording_region, app.universe.bounds().unwrap());
+        assert_eq!(app.recording_limit(), MAX_RECORDING_FRAMES);
+
+        let mut app = App::new(1024, 1024, 20, 10);
+        app.viewport_x = 1020;
+        app.universe.set(2, 0, 1);
+        app.start_recording();
+        assert_eq!(
+            app.recording_region,
+            Region {
+                x: 1020,
+                y: 0,
+                width: 20,
+                height: 10
+            }
+        );
+        // Cells past the right edge wrap around to the left of the grid
+        let frames = app.stop_recording();
+        assert_eq!(frames[0].len(), 200);
+        assert_eq!(frames[0][6], 1);
+        app.recording_region.width = 4096;
+        app.recording_region.height = 4096;
+        assert_eq!(app.recording_limit(), 2);
+    }
+
+    #[test]
+    fn edits_while_rewound_cut_the_timeline() {
+        let mut app = App::new(32, 32, 20, 10);
+        app.place_pattern(&patterns::GLIDER);
+        for _ in 0..4 {
+            app.step();
+        }
+        app.scrub(-2);
+        assert_eq!(app.generation, 2);
+        let frame = app.universe.live_cells();
+        (app.cursor_x, app.cursor_y) = (20, 20);
+        app.toggle_cell();
+        let edited = app.universe.live_cells();
+        assert_eq!(app.timeline.position(), None);
+        assert_eq!(app.timeline.newest_generation(), Some(2));
+
+        // Scrubbing again keeps the edit, and undo walks back through states
+        // that really existed
+        app.scrub(-1);
+        assert_eq!(app.generation, 1);
+        app.undo();
+        assert_eq!((app.universe.live_cells(), app.generation), (edited, 2));
+        app.undo();
+        assert_eq!((app.universe.live_cells(), app.generation), (frame, 2));
+    }
+
+    #[test]
+    fn fits_and_goes_to_cells() {
+        let mut app = App::new(256, 256, 40, 20);
+        app.universe.set(100, 100, 1);
+        app.universe.set(130, 110, 1);
+        app.fit_all();
+        assert_eq!(app.zoom, 1);
+        assert!(app.view_region().contains(100, 100) && app.view_region().contains(130, 110));
+        // Too tall for half blocks, so braille it is
+        app.universe.set(100, 170, 1);
+        app.fit_all();
+        assert_eq!(app.zoom, -1);
+        assert!(app.view_region().contains(100, 100) && app.view_region().contains(100, 170));
+
+        app.go_to("300, -1");
+        assert_eq!((app.cursor_x, app.cursor_y), (44, 255));
+        let (x, y) = app.cursor_on_screen();
+        assert!(app.view_region().contains(x, y));
+        app.go_to("nowhere");
+        assert_eq!((app.cursor_x, app.cursor_y), (44, 255));
+    }
 }
@@ -0,0 +1,100 @@
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use crate::app::DEFAULT_DENSITY;
+use crate::cli::Args;
+use crate::grid::{self, Grid};
+use crate::universe::Universe;
+
+const SIZES: &[usize] = &[1024, 2048, 4096];
+const DEFAULT_GENERATIONS: usize = 20;
+
+fn ms_per_generation(elapsed: Duration, generations: usize) -> f64 {
+    elapsed.as_secs_f64() * 1000.0 / generations.max(1) as f64
+}
+
+fn time(grid: &mut Grid, generations: usize, step: fn(&mut Grid)) -> f64 {
+    let start = Instant::now();
+    for _ in 0..generations {
+        step(grid);
+    }
+    ms_per_generation(start.elapsed(), generations)
+}
+
+/// Times the packed grid kernel, serial and across threads, with and without
+/// ages, against the original cell-by-cell step on random soups, printing a
+/// tab-separated table.
+pub fn run(args: &Args) -> io::Result<()> {
+    let generations = args.generations.unwrap_or(DEFAULT_GENERATIONS);
+    let sizes = match args.width {
+        Some(width) => vec![width],
+        None => SIZES.to_vec(),
+    };
+    let threads = args.threads.unwrap_or_else(grid::default_threads);
+    let threads_label = match threads {
+        1 => "1 thread".to_string(),
+        n => format!("{} threads", n),
+    };
+    let mut out = io::stdout().lock();
+    writeln!(
+        out,
+        "size\tscalar ms/gen\tpacked ms/gen\t{0} ms/gen\t{0}, no ages ms/gen\tspeedup",
+        threads_label
+    )?;
+
+    for size in sizes {
+        let mut grids: Vec<Grid> = (0..4)
+            .map(|_| {
+                let mut grid = Grid::new(size, args.height.unwrap_or(size));
+                grid.rule = args.rule.unwrap_or_default();
+                grid.topology = args.topology.unwrap_or_default();
+                let soup = grid.bounds().unwrap();
+                let density = args.density.unwrap_or(DEFAULT_DENSITY);
+                grid.randomize(
+                    soup,
+                    density,
+                    &mut fastrand::Rng::with_seed(args.seed.unwrap_or(1)),
+                );
+                grid
+            })
+            .collect();
+        grids[1].threads = 1;
+        grids[2].threads = threads;
+        grids[3].threads = threads;
+        grids[3].set_track_ages(false);
+
+        let scalar = time(&mut grids[0], generations, Grid::step_scalar);
+        let packed = time(&mut grids[1], generations, Grid::step);
+        let threaded = time(&mut grids[2], generations, Grid::step);
+        let bare = time(&mut grids[3], generations, Grid::step);
+        // Without ages every live cell reads as age 1, so that grid is only
+        // compared by position
+        let expected = grids[0].live_cells();
+        let positions = |cells: Vec<(isize, isize, u16)>| -> Vec<_> {
+            cells.into_iter().map(|(x, y, _)| (x, y)).collect()
+        };
+        let expected_positions = positions(expected.clone());
+        if grids[1..3].iter().any(|g| g.live_cells() != expected)
+            || grids[1..]
+                .iter()
+                .any(|g| positions(g.live_cells()) != expected_positions)
+        {
+            return Err(io::Error::other(format!(
+                "{}x{}: packed kernel diverged from the scalar step",
+                grids[0].width, grids[0].height
+            )));
+        }
+        writeln!(
+            out,
+            "{}x{}\t{:.2}\t{:.2}\t{:.2}\t{:.2}\t{:.1}x",
+            grids[0].width,
+            grids[0].height,
+            scalar,
+            packed,
+            threaded,
+            bare,
+            scalar / bare.max(f64::EPSILON)
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,389 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::app::{
+    App, DEFAULT_GRID_SIZE, MAX_GRID_SIZE, MAX_HASHLIFE_STEP_EXP, MAX_STEP_EXP, MAX_TICK_MS,
+    MAX_ZOOM, MIN_TICK_MS, MIN_ZOOM, SoupArea,
+};
+use crate::library::{self, Library};
+use crate::patterns;
+use crate::rule::Rule;
+use crate::session;
+use crate::topology::Topology;
+use crate::universe::Engine;
+
+pub const USAGE: &str = "\
+Usage: petri [OPTIONS]
+       petri run [OPTIONS] --generations <N>
+       petri bench [--generations <N>] [--width <N>] [--threads <N>] [--seed <N>]
+
+Options:
+  --width <N>           Grid width in cells [default: 256]
+  --height <N>          Grid height in cells [default: 256]
+  --rule <RULE>         Life-like rule, e.g. B3/S23, B36/S23 or a preset name
+  --topology <NAME>     Edge wrapping: torus, plane, klein or cross [default: torus]
+  --engine <NAME>       Simulation backend: grid, tiled or hashlife [default: grid]
+  --infinite            Simulate on an unbounded plane (same as --engine tiled)
+  --density <P>         Start from a random soup with fill probability P (0-1),
+                        also used by the randomize key [default: 0.25]
+  --seed <N>            Start from a random soup generated from seed N
+  --soup <N>            Confine random soups to an NxN square at the center
+                        of the grid (the 's' key uses this size) [default: 16]
+  --load <FILE>         Resume a saved session
+  --pattern <FILE>      Place a pattern file at the center of the grid
+  --library <DIR>       Directory of pattern files for the library panel
+                        [default: ~/.config/petri/patterns]
+  --tick-ms <MS>        Milliseconds per step (10-1000) [default: 100]
+  --step-exp <K>        Advance 2^K generations per step [default: 0]
+  --threads <N>         Threads used to step the grid [default: CPU count]
+  --zoom <N>            Starting zoom level (-1-8) [default: 1]
+  -h, --help            Print this help
+
+Batch options (petri run):
+  --generations <N>     Number of generations to simulate (petri bench: per
+                        board size) [bench default: 20]
+  --every <N>           Print the population every N generations
+  --output <FILE>       Write the final state; .petri saves a session, other
+                        extensions select a pattern format (default RLE)
+";
+
+#[derive(Default)]
+pub struct Args {
+    pub headless: bool,
+    pub bench: bool,
+    pub width: Option<usize>,
+    pub height: Option<usize>,
+    pub rule: Option<Rule>,
+    pub topology: Option<Topology>,
+    pub engine: Option<Engine>,
+    pub density: Option<f64>,
+    pub seed: Option<u64>,
+    pub soup: Option<usize>,
+    pub tick_ms: Option<u64>,
+    pub step_exp: Option<u32>,
+    pub threads: Option<usize>,
+    pub zoom: Option<i32>,
+    pub load: Option<PathBuf>,
+    pub pattern: Option<PathBuf>,
+    pub library: Option<PathBuf>,
+    pub generations: Option<usize>,
+    pub every: Option<usize>,
+    pub output: Option<PathBuf>,
+    pub help: bool,
+}
+
+const MAX_THREADS: usize = 256;
+
+fn number<T: std::str::FromStr>(flag: &str, value: String) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{}: expected a number, got '{}'", flag, value))
+}
+
+fn in_range<T: PartialOrd + std::fmt::Display>(
+    flag: &str,
+    value: T,
+    min: T,
+    max: T,
+) -> Result<T, String> {
+    if value < min || value > max {
+        return Err(format!("{} must be between {} and {}", flag, min, max));
+    }
+    Ok(value)
+}
+
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Args, String> {
+    let mut parsed = Args::default();
+    let mut args = args.into_iter().peekable();
+
+    match args.peek().map(String::as_str) {
+        Some("run") => parsed.headless = true,
+        Some("bench") => parsed.bench = true,
+        _ => {}
+    }
+    if parsed.headless || parsed.bench {
+        args.next();
+    }
+
+    while let Some(arg) = args.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => {
+                (flag.to_string(), Some(value.to_string()))
+            }
+            _ => (arg, None),
+        };
+        let mut value = || {
+            inline
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("{} requires a value", flag))
+        };
+
+        match flag.as_str() {
+            "-h" | "--help" => parsed.help = true,
+            "--infinite" => parsed.engine = Some(Engine::Tiled),
+            "--engine" => {
+                let v = value()?;
+                parsed.engine = Some(v.parse().map_err(|e| format!("--engine: {}", e))?);
+            }
+            "--rule" => {
+                let v = value()?;
+                parsed.rule = Some(v.parse().map_err(|e| format!("--rule: {}", e))?);
+            }
+            "--topology" => {
+                let v = value()?;
+                parsed.topology = Some(v.parse().map_err(|e| format!("--topology: {}", e))?);
+            }
+            "--width" => {
+                let n = number(&flag, value()?)?;
+                parsed.width = Some(in_range(&flag, n, 1, MAX_GRID_SIZE)?);
+            }
+            "--height" => {
+                let n = number(&flag, value()?)?;
+                parsed.height = Some(in_range(&flag, n, 1, MAX_GRID_SIZE)?);
+            }
+            "--density" => {
+                let p = number(&flag, value()?)?;
+                parsed.density = Some(in_range(&flag, p, 0.0, 1.0)?);
+            }
+            "--seed" => parsed.seed = Some(number(&flag, value()?)?),
+            "--soup" => {
+                let n = number(&flag, value()?)?;
+                parsed.soup = Some(in_range(&flag, n, 1, MAX_GRID_SIZE)?);
+            }
+            "--tick-ms" => {
+                let ms = number(&flag, value()?)?;
+                parsed.tick_ms = Some(in_range(&flag, ms, MIN_TICK_MS, MAX_TICK_MS)?);
+            }
+            "--step-exp" => {
+                let k = number(&flag, value()?)?;
+                parsed.step_exp = Some(in_range(&flag, k, 0, MAX_HASHLIFE_STEP_EXP)?);
+            }
+            "--threads" => {
+                let n = number(&flag, value()?)?;
+                parsed.threads = Some(in_range(&flag, n, 1, MAX_THREADS)?);
+            }
+            "--zoom" => {
+                let zoom = number(&flag, value()?)?;
+                parsed.zoom = Some(in_range(&flag, zoom, MIN_ZOOM, MAX_ZOOM)?);
+            }
+            "--load" => parsed.load = Some(PathBuf::from(value()?)),
+            "--pattern" => parsed.pattern = Some(PathBuf::from(value()?)),
+            "--library" => parsed.library = Some(PathBuf::from(value()?)),
+            "--generations" => parsed.generations = Some(number(&flag, value()?)?),
+            "--every" => parsed.every = Some(number::<usize>(&flag, value()?)?.max(1)),
+            "--output" => parsed.output = Some(PathBuf::from(value()?)),
+            _ => return Err(format!("unknown argument '{}'", flag)),
+        }
+    }
+
+    if parsed.load.is_some() && (parsed.width.is_some() || parsed.height.is_some()) {
+        return Err("--width and --height cannot be combined with --load".to_string());
+    }
+    if parsed.load.is_some()
+        && (parsed.seed.is_some() || parsed.density.is_some() || parsed.soup.is_some())
+    {
+        return Err("--seed, --density and --soup cannot be combined with --load".to_string());
+    }
+    let engine = parsed.engine.unwrap_or_default();
+    if engine != Engine::Grid
+        && (parsed.width.is_some() || parsed.height.is_some() || parsed.topology.is_some())
+    {
+        return Err(format!(
+            "--width, --height and --topology do not apply to the {} engine",
+            engine
+        ));
+    }
+    if engine != Engine::HashLife && parsed.step_exp.is_some_and(|k| k > MAX_STEP_EXP) {
+        return Err(format!(
+            "--step-exp above {} requires --engine hashlife",
+            MAX_STEP_EXP
+        ));
+    }
+    if !parsed.headless {
+        let batch_only = [
+            (
+                "--generations",
+                parsed.generations.is_some() && !parsed.bench,
+            ),
+            ("--every", parsed.every.is_some()),
+            ("--output", parsed.output.is_some()),
+        ];
+        if let Some((flag, _)) = batch_only.iter().find(|(_, given)| *given) {
+            return Err(format!("{} is only valid with 'petri run'", flag));
+        }
+    } else if parsed.generations.is_none() && !parsed.help {
+        return Err("'petri run' requires --generations".to_string());
+    }
+
+    Ok(parsed)
+}
+
+/// Builds the initial application state described by the arguments, reading any
+/// session or pattern files they name.
+pub fn build_app(args: &Args, view_width: usize, view_height: usize) -> Result<App, String> {
+    let mut app = match args.engine.unwrap_or_default() {
+        Engine::Grid => App::new(
+            args.width.unwrap_or(DEFAULT_GRID_SIZE),
+            args.height.unwrap_or(DEFAULT_GRID_SIZE),
+            view_width,
+            view_height,
+        ),
+        engine => {
+            let mut app = App::with_universe(engine.create((0, 0)), view_width, view_height);
+            // Start with the origin in the middle of the screen
+            let (cols, rows) = app.visible_size();
+            app.viewport_x = -(cols as isize / 2);
+            app.viewport_y = -(rows as isize / 2);
+            app
+        }
+    };
+
+    if let Some(threads) = args.threads {
+        app.set_threads(threads);
+    }
+
+    if let Some(path) = &args.load {
+        let session = session::load(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        session.restore(&mut app);
+    }
+
+    if let Some(topology) = args.topology {
+        app.universe.set_topology(topology);
+    }
+    if let Some(density) = args.density {
+        app.density = density;
+    }
+    if let Some(size) = args.soup {
+        app.soup_size = size;
+    }
+    if args.seed.is_some() || args.density.is_some() || args.soup.is_some() {
+        let area = match args.soup {
+            Some(size) => SoupArea::Centered(size),
+            None => SoupArea::Grid,
+        };
+        app.soup(area, args.seed.unwrap_or_else(|| fastrand::u64(..)));
+    }
+
+    if let Some(path) = &args.pattern {
+        let pattern = patterns::load(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        if let Some(bounds) = app.universe.bounds() {
+            app.cursor_x = bounds.width as isize / 2;
+            app.cursor_y = bounds.height as isize / 2;
+        }
+        app.place_pattern(&pattern);
+        if let Some(rule) = pattern.rule {
+            app.universe.set_rule(rule)?;
+        }
+        app.patterns.push(pattern);
+    }
+
+    // A missing default library directory just leaves the built-ins
+    if let Some(dir) = &args.library {
+        app.library = Library::load(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    } else if let Some(dir) = library::default_dir().filter(|d| d.is_dir() && !args.headless)
+        && let Ok(loaded) = Library::load(&dir)
+    {
+        app.library = loaded;
+    }
+
+    if let Some(rule) = args.rule {
+        app.universe.set_rule(rule)?;
+    }
+    if let Some(k) = args.step_exp {
+        app.step_exp = k;
+    }
+    if let Some(ms) = args.tick_ms {
+        app.tick_rate = Duration::from_millis(ms);
+    }
+    if let Some(zoom) = args.zoom {
+        app.zoom = zoom;
+    }
+
+    Ok(app)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_str(args: &str) -> Result<Args, String> {
+        parse(args.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn parses_options() {
+        let args = parse_str("--width 64 --height=32 --seed 7 --density 0.5 --zoom 0").unwrap();
+        assert!(!args.headless);
+        assert_eq!((args.width, args.height), (Some(64), Some(32)));
+        assert_eq!(args.seed, Some(7));
+        assert_eq!(args.density, Some(0.5));
+        assert_eq!(args.zoom, Some(0));
+
+        let args = parse_str("run --rule highlife --generations 10 --every 5").unwrap();
+        assert!(args.headless);
+        assert_eq!(args.rule.unwrap().to_string(), "B36/S23");
+        assert_eq!((args.generations, args.every), (Some(10), Some(5)));
+
+        let args = parse_str("bench --generations 3 --width 512 --threads 2").unwrap();
+        assert!(args.bench && !args.headless);
+        assert_eq!((args.generations, args.width), (Some(3), Some(512)));
+        assert_eq!(args.threads, Some(2));
+    }
+
+    #[test]
+    fn rejects_invalid_options() {
+        assert!(parse_str("--width 0").is_err());
+        assert!(parse_str("--density 1.5").is_err());
+        assert!(parse_str("--tick-ms fast").is_err());
+        assert!(parse_str("--generations 10").is_err());
+        assert!(parse_str("run").is_err());
+        assert!(parse_str("--load a.petri --width 10").is_err());
+        assert!(parse_str("--load a.petri --seed 7").is_err());
+        assert!(parse_str("--load a.petri --soup 16").is_err());
+        assert!(parse_str("--infinite --topology klein").is_err());
+        assert!(parse_str("--engine life").is_err());
+        assert!(parse_str("--step-exp 20").is_err());
+        assert!(parse_str("--engine hashlife --step-exp 20").is_ok());
+        assert!(parse_str("--bogus").is_err());
+        assert!(parse_str("bench --every 5").is_err());
+        assert!(parse_str("--threads 0").is_err());
+    }
+
+    #[test]
+    fn same_seed_builds_same_soup() {
+        let args = parse_str("--width 40 --height 30 --seed 1234").unwrap();
+        let a = build_app(&args, 10, 10).unwrap();
+        let b = build_app(&args, 10, 10).unwrap();
+        assert_eq!(a.universe.live_cells(), b.universe.live_cells());
+        assert!(a.universe.population() > 0);
+        assert_eq!(a.seed, Some(1234));
+    }
+
+    #[test]
+    fn soup_is_centered() {
+        let args = parse_str("--width 40 --height 30 --soup 4 --density 1").unwrap();
+        let app = build_app(&args, 10, 10).unwrap();
+        let live = app.universe.live_cells();
+        assert_eq!(live.len(), 16);
+        assert!(
+            live.iter()
+                .all(|&(x, y, _)| (18..22).contains(&x) && (13..17).contains(&y))
+        );
+    }
+
+    #[test]
+    fn infinite_soup_is_centered_on_origin() {
+        let args = parse_str("--infinite --soup 4 --density 1 --rule B2/S").unwrap();
+        let app = build_app(&args, 10, 10).unwrap();
+        assert_eq!(app.universe.bounds(), None);
+        assert_eq!(app.universe.rule().to_string(), "B2/S");
+        assert!(
+            app.universe
+                .live_cells()
+                .iter()
+                .all(|&(x, y, _)| (-2..2).contains(&x) && (-2..2).contains(&y))
+        );
+        assert!(build_app(&parse_str("--infinite --rule B0/S8").unwrap(), 10, 10).is_err());
+    }
+}
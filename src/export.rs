@@ -1,6 +1,8 @@
 use std::fs::File;
 use std::io;
+use std::path::Path;
 
+use crate::patterns::{self, Format, Pattern};
 use crate::ui::age_color;
 use ratatui::style::Color;
 
@@ -11,6 +13,21 @@ fn color_to_rgb(color: Color) -> [u8; 3] {
     }
 }
 
+pub fn timestamped_filename(extension: &str) -> String {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    format!("petri_{}.{}", timestamp, extension)
+}
+
+/// Writes `pattern` to `path` in the format implied by its extension, falling back to RLE.
+pub fn write_pattern(pattern: &Pattern, path: &Path) -> io::Result<Format> {
+    let format = Format::from_path(path).unwrap_or(Format::Rle);
+    std::fs::write(path, patterns::write(pattern, format))?;
+    Ok(format)
+}
+
 pub fn encode_gif(
     frames: &[Vec<u16>],
     width: usize,
@@ -21,18 +38,14 @@ pub fn encode_gif(
     let img_width = (width as u16) * cell_size;
     let img_height = (height as u16) * cell_size;
 
-    let timestamp = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
-    let filename = format!("petri_{}.gif", timestamp);
+    let filename = timestamped_filename("gif");
 
     let file = File::create(&filename)?;
-    let mut encoder = gif::Encoder::new(file, img_width, img_height, &[])
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+    let mut encoder =
+        gif::Encoder::new(file, img_width, img_height, &[]).map_err(io::Error::other)?;
     encoder
         .set_repeat(gif::Repeat::Infinite)
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        .map_err(io::Error::other)?;
 
     let delay = tick_ms / 10; // GIF delay is in centiseconds
 
@@ -72,9 +85,7 @@ pub fn encode_gif(
         if prev_cells.is_some() {
             gif_frame.dispose = gif::DisposalMethod::Keep;
         }
-        encoder
-            .write_frame(&gif_frame)
-            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        encoder.write_frame(&gif_frame).map_err(io::Error::other)?;
 
         prev_cells = Some(frame_cells);
     }
@@ -1,35 +1,211 @@
+use crate::rule::Rule;
+use crate::topology::Topology;
+use crate::universe::{Engine, Region, Universe};
+
+const WORD: usize = u64::BITS as usize;
+
+/// Finite grid stored as packed rows of 64 cells per word. Ages are kept in a
+/// side buffer only while something needs them (see [`Universe::set_track_ages`]).
 pub struct Grid {
     pub width: usize,
     pub height: usize,
-    pub cells: Vec<u16>,
-    scratch: Vec<u16>, // scratch buffer
+    pub rule: Rule,
+    pub topology: Topology,
+    /// Words per row; bits past `width` in the last word are always zero.
+    stride: usize,
+    bits: Vec<u64>,
+    ages: Option<Vec<u16>>,
+    /// Number of row bands stepped in parallel.
+    pub threads: usize,
+    /// Per tile: whether any of its cells changed in the last step (or by an edit).
+    changed: Vec<bool>,
+    /// Per tile: whether the last step computed it rather than copying it.
+    active: Vec<bool>,
+    /// Rule of the last step; a new rule invalidates the change tracking.
+    stepped_rule: Rule,
+    // scratch buffers
+    next: Vec<u64>,
+    padded: Vec<u64>,
+    scratch_ages: Vec<u16>,
+}
+
+/// Rows per tile; a tile is one word (64 cells) wide. Tiles are skipped when
+/// nothing in or next to them changed in the previous generation.
+const TILE_ROWS: usize = 16;
+/// Bands thinner than this are not worth a thread of their own; a multiple of
+/// `TILE_ROWS` so each tile belongs to one band.
+const MIN_BAND_ROWS: usize = 16;
+
+pub fn default_threads() -> usize {
+    std::thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// The packed step for a band of rows, reading from the padded copy of the grid.
+struct Kernel<'a> {
+    padded: &'a [u64],
+    /// Words per padded row.
+    pw: usize,
+    stride: usize,
+    width: usize,
+    /// Mask of the bits in the last word of each row that lie inside the grid.
+    tail: u64,
+    birth: Vec<u8>,
+    survival: Vec<u8>,
+}
+
+/// Adds a one-bit input to a four-bit counter spread across bit planes.
+fn add(planes: &mut [u64; 4], input: u64) {
+    let mut carry = input;
+    for plane in planes.iter_mut() {
+        let sum = *plane ^ carry;
+        carry &= *plane;
+        *plane = sum;
+    }
+}
+
+/// The rows one thread steps: `old` and `next` hold them packed, `ages` (when
+/// tracked) cell by cell, and `active`/`changed` one flag per tile.
+struct Band<'a> {
+    first_row: usize,
+    old: &'a [u64],
+    next: &'a mut [u64],
+    ages: Option<&'a mut [u16]>,
+    active: &'a [bool],
+    changed: &'a mut [bool],
+}
+
+impl Kernel<'_> {
+    /// Next state of padded word `i` of padded row `y + 1`, in padded coordinates.
+    fn word(&self, y: usize, i: usize) -> u64 {
+        let pw = self.pw;
+        let rows = [y, y + 1, y + 2].map(|r| &self.padded[r * pw..(r + 1) * pw]);
+        let mut planes = [0u64; 4];
+        for (r, row) in rows.iter().enumerate() {
+            let word = row[i];
+            // Neighbors to the west and east, carried across word boundaries
+            let west = (word << 1) | if i > 0 { row[i - 1] >> (WORD - 1) } else { 0 };
+            let east = (word >> 1)
+                | if i + 1 < pw {
+                    row[i + 1] << (WORD - 1)
+                } else {
+                    0
+                };
+            add(&mut planes, west);
+            add(&mut planes, east);
+            if r != 1 {
+                add(&mut planes, word);
+            }
+        }
+
+        let count = |n: u8| {
+            planes.iter().enumerate().fold(!0u64, |acc, (bit, &plane)| {
+                acc & if n >> bit & 1 == 1 { plane } else { !plane }
+            })
+        };
+        let alive = rows[1][i];
+        let born = self.birth.iter().fold(0, |acc, &n| acc | count(n));
+        let survives = self.survival.iter().fold(0, |acc, &n| acc | count(n));
+        (born & !alive) | (survives & alive)
+    }
+
+    /// Computes the band's rows of the next generation. Words in inactive tiles
+    /// are copied unchanged; tiles whose words changed are flagged in `changed`.
+    fn band(&self, band: Band) {
+        let stride = self.stride;
+        band.changed.fill(false);
+        let mut line = vec![0u64; self.pw];
+        for (r, out) in band.next.chunks_mut(stride).enumerate() {
+            let tiles = &band.active[r / TILE_ROWS * stride..][..stride];
+            let old = &band.old[r * stride..][..stride];
+            if !tiles.contains(&true) {
+                out.copy_from_slice(old);
+                continue;
+            }
+            // Output word k straddles padded words k and k + 1
+            for (i, word) in line.iter_mut().enumerate() {
+                let needed = tiles.get(i).copied().unwrap_or(false)
+                    || (i > 0 && tiles.get(i - 1).copied().unwrap_or(false));
+                if needed {
+                    *word = self.word(band.first_row + r, i);
+                }
+            }
+
+            for (k, word) in out.iter_mut().enumerate() {
+                if !tiles[k] {
+                    *word = old[k];
+                    continue;
+                }
+                // Drop the border again, shifting back to grid coordinates
+                let high = line.get(k + 1).map_or(0, |w| w << (WORD - 1));
+                *word = (line[k] >> 1) | high;
+                if k == stride - 1 {
+                    *word &= self.tail;
+                }
+                if *word != old[k] {
+                    band.changed[r / TILE_ROWS * stride + k] = true;
+                }
+            }
+        }
+
+        if let Some(ages) = band.ages {
+            for (k, (&old, &new)) in band.old.iter().zip(band.next.iter()).enumerate() {
+                let mut changed = old | new;
+                let base = (k / stride) * self.width + (k % stride) * WORD;
+                while changed != 0 {
+                    let bit = changed.trailing_zeros() as usize;
+                    changed &= changed - 1;
+                    let age = &mut ages[base + bit];
+                    *age = match (old >> bit & 1, new >> bit & 1) {
+                        (1, 1) => age.saturating_add(1),
+                        (0, 1) => 1,
+                        _ => 0,
+                    };
+                }
+            }
+        }
+    }
 }
 
 impl Grid {
     pub fn new(width: usize, height: usize) -> Self {
-        let size = width * height;
+        let stride = width.div_ceil(WORD);
         Self {
             width,
             height,
-            cells: vec![0; size],
-            scratch: vec![0; size],
+            rule: Rule::default(),
+            topology: Topology::default(),
+            stride,
+            bits: vec![0; stride * height],
+            ages: Some(vec![0; width * height]),
+            threads: default_threads(),
+            changed: vec![true; stride * height.div_ceil(TILE_ROWS)],
+            active: vec![true; stride * height.div_ceil(TILE_ROWS)],
+            stepped_rule: Rule::default(),
+            next: vec![0; stride * height],
+            padded: Vec::new(),
+            scratch_ages: Vec::new(),
         }
     }
 
-    pub fn population(&self) -> usize {
-        self.cells.iter().filter(|&&c| c > 0).count()
+    fn wrap_index(&self, x: isize, y: isize) -> Option<(usize, usize)> {
+        self.topology.wrap(x, y, self.width, self.height)
+    }
+
+    fn alive(&self, x: usize, y: usize) -> bool {
+        self.bits[y * self.stride + x / WORD] >> (x % WORD) & 1 == 1
+    }
+
+    /// Whether the cell at a possibly out-of-range position is alive, following
+    /// the topology across the edges.
+    fn alive_wrapped(&self, x: isize, y: isize) -> bool {
+        self.wrap_index(x, y).is_some_and(|(x, y)| self.alive(x, y))
     }
 
     pub fn count_neighbors(&self, x: usize, y: usize) -> u8 {
         let mut count = 0u8;
         for dy in [-1isize, 0, 1] {
             for dx in [-1isize, 0, 1] {
-                if dx == 0 && dy == 0 {
-                    continue;
-                }
-                let nx = (x as isize + dx).rem_euclid(self.width as isize) as usize;
-                let ny = (y as isize + dy).rem_euclid(self.height as isize) as usize;
-                if self.cells[ny * self.width + nx] > 0 {
+                if (dx != 0 || dy != 0) && self.alive_wrapped(x as isize + dx, y as isize + dy) {
                     count += 1;
                 }
             }
@@ -37,43 +213,344 @@ impl Grid {
         count
     }
 
+    /// Copies the grid into `padded`, `width + 2` by `height + 2` cells with a
+    /// one-cell border holding whatever lies across each edge.
+    fn fill_padded(&mut self) {
+        let pw = (self.width + 2).div_ceil(WORD);
+        let mut padded = std::mem::take(&mut self.padded);
+        padded.clear();
+        padded.resize(pw * (self.height + 2), 0);
+        let set = |row: &mut [u64], x: usize| row[x / WORD] |= 1 << (x % WORD);
+
+        for py in 0..self.height + 2 {
+            let y = py as isize - 1;
+            let row = &mut padded[py * pw..(py + 1) * pw];
+            if (1..=self.height).contains(&py) {
+                // Shift the whole row one bit along to make room for the west border
+                let src = &self.bits[(py - 1) * self.stride..py * self.stride];
+                for (i, &word) in src.iter().enumerate() {
+                    row[i] |= word << 1;
+                    if i + 1 < pw {
+                        row[i + 1] |= word >> (WORD - 1);
+                    }
+                }
+                if self.alive_wrapped(-1, y) {
+                    set(row, 0);
+                }
+                if self.alive_wrapped(self.width as isize, y) {
+                    set(row, self.width + 1);
+                }
+            } else {
+                for px in 0..self.width + 2 {
+                    if self.alive_wrapped(px as isize - 1, y) {
+                        set(row, px);
+                    }
+                }
+            }
+        }
+        self.padded = padded;
+    }
+
+    fn tile(&self, x: usize, y: usize) -> usize {
+        y / TILE_ROWS * self.stride + x / WORD
+    }
+
+    /// Marks the tiles that may change this step: those that changed last step
+    /// and their neighbors. Where the edges are joined with a twist, or the grid
+    /// doesn't divide evenly into tiles, every tile on the edge is included too.
+    fn mark_active(&mut self) {
+        if self.rule != self.stepped_rule {
+            self.changed.fill(true);
+            self.stepped_rule = self.rule;
+        }
+        let (tw, th) = (self.stride, self.height.div_ceil(TILE_ROWS));
+        let aligned = self.width.is_multiple_of(WORD) && self.height.is_multiple_of(TILE_ROWS);
+        let wrap = self.topology == Topology::Torus && aligned;
+        let edges = !wrap && self.topology != Topology::Plane;
+
+        self.active.fill(false);
+        for ty in 0..th {
+            for tx in 0..tw {
+                if edges && (ty == 0 || ty + 1 == th || tx == 0 || tx + 1 == tw) {
+                    self.active[ty * tw + tx] = true;
+                }
+                if !self.changed[ty * tw + tx] {
+                    continue;
+                }
+                for dy in -1..=1 {
+                    for dx in -1..=1 {
+                        let (nx, ny) = (tx as isize + dx, ty as isize + dy);
+                        let (nx, ny) = if wrap {
+                            (nx.rem_euclid(tw as isize), ny.rem_euclid(th as isize))
+                        } else if (0..tw as isize).contains(&nx) && (0..th as isize).contains(&ny) {
+                            (nx, ny)
+                        } else {
+                            continue;
+                        };
+                        self.active[ny as usize * tw + nx as usize] = true;
+                    }
+                }
+            }
+        }
+    }
+
     pub fn step(&mut self) {
+        self.fill_padded();
+        self.mark_active();
+        let kernel = Kernel {
+            padded: &self.padded,
+            pw: (self.width + 2).div_ceil(WORD),
+            stride: self.stride,
+            width: self.width,
+            tail: match self.width % WORD {
+                0 => !0,
+                bits => (1 << bits) - 1,
+            },
+            birth: (0..=8).filter(|&n| self.rule.born(n)).collect(),
+            survival: (0..=8).filter(|&n| self.rule.survives(n)).collect(),
+        };
+
+        let band_rows = self
+            .height
+            .div_ceil(self.threads.max(1))
+            .max(MIN_BAND_ROWS)
+            .next_multiple_of(TILE_ROWS);
+        let bands = self.height.div_ceil(band_rows);
+        // Each band writes its own rows of `next`, `ages` and `changed`; the padded
+        // copy already holds the rows across band and grid edges
+        let mut age_bands: Vec<Option<&mut [u16]>> = match &mut self.ages {
+            Some(ages) => ages.chunks_mut(band_rows * self.width).map(Some).collect(),
+            None => (0..bands).map(|_| None).collect(),
+        };
+        let tiles = band_rows / TILE_ROWS * self.stride;
+        let parts = self
+            .bits
+            .chunks(band_rows * self.stride)
+            .zip(self.next.chunks_mut(band_rows * self.stride))
+            .zip(age_bands.drain(..))
+            .zip(
+                self.active
+                    .chunks(tiles)
+                    .zip(self.changed.chunks_mut(tiles)),
+            )
+            .enumerate()
+            .map(|(i, (((old, next), ages), (active, changed)))| Band {
+                first_row: i * band_rows,
+                old,
+                next,
+                ages,
+                active,
+                changed,
+            });
+
+        if bands == 1 {
+            parts.for_each(|band| kernel.band(band));
+        } else {
+            std::thread::scope(|scope| {
+                for band in parts {
+                    let kernel = &kernel;
+                    scope.spawn(move || kernel.band(band));
+                }
+            });
+        }
+        std::mem::swap(&mut self.bits, &mut self.next);
+    }
+
+    /// The original cell-by-cell step, kept as a reference for the packed kernel
+    /// and for `petri bench`. Tracks ages.
+    pub fn step_scalar(&mut self) {
+        self.set_track_ages(true);
+        let mut next = std::mem::take(&mut self.scratch_ages);
+        next.resize(self.width * self.height, 0);
+        let ages = self.ages.as_ref().unwrap();
         for y in 0..self.height {
             for x in 0..self.width {
                 let idx = y * self.width + x;
                 let neighbors = self.count_neighbors(x, y);
-                let age = self.cells[idx];
+                let age = ages[idx];
 
-                self.scratch[idx] = if age > 0 && (neighbors == 2 || neighbors == 3) {
+                next[idx] = if age > 0 && self.rule.survives(neighbors) {
                     age.saturating_add(1)
-                } else if age == 0 && neighbors == 3 {
+                } else if age == 0 && self.rule.born(neighbors) {
                     1
                 } else {
                     0
                 };
             }
         }
-        std::mem::swap(&mut self.cells, &mut self.scratch);
+        self.scratch_ages = self.ages.replace(next).unwrap();
+        self.sync_bits();
     }
 
-    pub fn randomize(&mut self) {
-        // TODO: allow for custom start positions
-        for y in 0..self.height {
-            for x in 0..self.width {
-                let alive = fastrand::f64() < 0.25;
-                if alive {
-                    self.cells[y * self.width + x] = 1;
-                } else {
-                    self.cells[y * self.width + x] = 0;
+    /// Rebuilds the packed rows from the age buffer.
+    fn sync_bits(&mut self) {
+        let ages = self.ages.as_ref().unwrap();
+        self.changed.fill(true);
+        self.bits.fill(0);
+        for (idx, &age) in ages.iter().enumerate() {
+            if age > 0 {
+                let (x, y) = (idx % self.width, idx / self.width);
+                self.bits[y * self.stride + x / WORD] |= 1 << (x % WORD);
+            }
+        }
+    }
+}
+
+impl Universe for Grid {
+    fn get(&self, x: isize, y: isize) -> u16 {
+        if !(0..self.width as isize).contains(&x) || !(0..self.height as isize).contains(&y) {
+            return 0;
+        }
+        let (x, y) = (x as usize, y as usize);
+        match &self.ages {
+            Some(ages) => ages[y * self.width + x],
+            None => self.alive(x, y) as u16,
+        }
+    }
+
+    fn set(&mut self, x: isize, y: isize, age: u16) {
+        if !(0..self.width as isize).contains(&x) || !(0..self.height as isize).contains(&y) {
+            return;
+        }
+        let (x, y) = (x as usize, y as usize);
+        let tile = self.tile(x, y);
+        self.changed[tile] = true;
+        let word = &mut self.bits[y * self.stride + x / WORD];
+        if age > 0 {
+            *word |= 1 << (x % WORD);
+        } else {
+            *word &= !(1 << (x % WORD));
+        }
+        if let Some(ages) = &mut self.ages {
+            ages[y * self.width + x] = age;
+        }
+    }
+
+    fn wrap(&self, x: isize, y: isize) -> Option<(isize, isize)> {
+        self.wrap_index(x, y).map(|(x, y)| (x as isize, y as isize))
+    }
+
+    fn step(&mut self) {
+        Grid::step(self);
+    }
+
+    fn population(&self) -> usize {
+        self.bits.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    fn clear(&mut self) {
+        self.bits.fill(0);
+        self.changed.fill(true);
+        if let Some(ages) = &mut self.ages {
+            ages.fill(0);
+        }
+    }
+
+    fn live_cells(&self) -> Vec<(isize, isize, u16)> {
+        let mut cells = Vec::new();
+        for (k, &word) in self.bits.iter().enumerate() {
+            let mut word = word;
+            while word != 0 {
+                let bit = word.trailing_zeros() as usize;
+                word &= word - 1;
+                let (x, y) = ((k % self.stride) * WORD + bit, k / self.stride);
+                cells.push((x as isize, y as isize, self.get(x as isize, y as isize)));
+            }
+        }
+        cells
+    }
+
+    fn bounds(&self) -> Option<Region> {
+        Some(Region {
+            x: 0,
+            y: 0,
+            width: self.width,
+            height: self.height,
+        })
+    }
+
+    fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    fn set_rule(&mut self, rule: Rule) -> Result<(), String> {
+        self.rule = rule;
+        Ok(())
+    }
+
+    fn topology(&self) -> Option<Topology> {
+        Some(self.topology)
+    }
+
+    fn set_topology(&mut self, topology: Topology) {
+        self.topology = topology;
+        // Tiles along the edges may settle differently under the new edges
+        self.changed.fill(true);
+    }
+
+    fn engine(&self) -> Engine {
+        Engine::Grid
+    }
+
+    fn set_track_ages(&mut self, track: bool) {
+        match (track, self.ages.is_some()) {
+            (true, false) => {
+                // Ages restart at 1 for every live cell
+                let mut ages = vec![0; self.width * self.height];
+                for y in 0..self.height {
+                    for x in 0..self.width {
+                        ages[y * self.width + x] = self.alive(x, y) as u16;
+                    }
                 }
+                self.ages = Some(ages);
+            }
+            (false, true) => self.ages = None,
+            _ => {}
+        }
+    }
+
+    fn active_at(&self, x: isize, y: isize) -> bool {
+        (0..self.width as isize).contains(&x)
+            && (0..self.height as isize).contains(&y)
+            && self.active[self.tile(x as usize, y as usize)]
+    }
+
+    fn set_threads(&mut self, threads: usize) {
+        self.threads = threads.max(1);
+    }
+
+    fn packed_rows(&self) -> Option<(&[u64], usize)> {
+        Some((&self.bits, self.stride))
+    }
+
+    fn randomize(&mut self, region: Region, density: f64, rng: &mut fastrand::Rng) {
+        let Some(region) = region.intersect(&self.bounds().unwrap()) else {
+            return;
+        };
+        for y in region.y..region.y + region.height as isize {
+            for x in region.x..region.x + region.width as isize {
+                let alive = rng.f64() < density;
+                self.set(x, y, alive as u16);
             }
         }
     }
 
-    pub fn clear(&mut self) {
-        self.cells.fill(0);
+    fn snapshot(&self, region: Region) -> Vec<u16> {
+        if let Some(ages) = &self.ages
+            && region == self.bounds().unwrap()
+        {
+            return ages.clone();
+        }
+        let mut cells = Vec::with_capacity(region.width * region.height);
+        for y in region.y..region.y + region.height as isize {
+            for x in region.x..region.x + region.width as isize {
+                cells.push(self.get(x, y));
+            }
+        }
+        cells
     }
 }
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -82,7 +559,7 @@ mod tests {
     fn grid_from_points(width: usize, height: usize, alive: &[(usize, usize)]) -> Grid {
         let mut grid = Grid::new(width, height);
         for &(x, y) in alive {
-            grid.cells[y * width + x] = 1;
+            grid.set(x as isize, y as isize, 1);
         }
         grid
     }
@@ -92,7 +569,7 @@ mod tests {
         let mut result = Vec::new();
         for y in 0..grid.height {
             for x in 0..grid.width {
-                if grid.cells[y * grid.width + x] > 0 {
+                if grid.get(x as isize, y as isize) > 0 {
                     result.push((x, y));
                 }
             }
@@ -139,4 +616,193 @@ mod tests {
         grid.step();
         assert!(alive_cells(&grid).is_empty());
     }
+
+    #[test]
+    fn plane_edges_are_dead() {
+        // A blinker lying along the top edge loses the cell that would be born above it
+        let edge = [(0, 0), (1, 0), (2, 0)];
+        let mut torus = grid_from_points(6, 6, &edge);
+        torus.step();
+        assert_eq!(alive_cells(&torus), vec![(1, 0), (1, 1), (1, 5)]);
+
+        let mut plane = grid_from_points(6, 6, &edge);
+        plane.topology = Topology::Plane;
+        plane.step();
+        assert_eq!(alive_cells(&plane), vec![(1, 0), (1, 1)]);
+    }
+
+    #[test]
+    fn klein_bottle_mirrors_across_top_edge() {
+        // The cell born across the top edge appears mirrored on the bottom row
+        let mut grid = grid_from_points(6, 6, &[(0, 0), (1, 0), (2, 0)]);
+        grid.topology = Topology::KleinBottle;
+        grid.step();
+        assert_eq!(alive_cells(&grid), vec![(1, 0), (1, 1), (4, 5)]);
+    }
+
+    #[test]
+    fn seeded_randomize_is_reproducible_and_confined() {
+        let region = Region {
+            x: 4,
+            y: 2,
+            width: 8,
+            height: 5,
+        };
+        let mut a = Grid::new(16, 16);
+        let mut b = Grid::new(16, 16);
+        a.randomize(region, 0.5, &mut fastrand::Rng::with_seed(42));
+        b.randomize(region, 0.5, &mut fastrand::Rng::with_seed(42));
+        assert_eq!(a.live_cells(), b.live_cells());
+
+        let cells = alive_cells(&a);
+        assert!(!cells.is_empty());
+        assert!(
+            cells
+                .iter()
+                .all(|&(x, y)| (4..12).contains(&x) && (2..7).contains(&y))
+        );
+    }
+
+    #[test]
+    fn seeds_rule_births_on_two() {
+        // Under B2/S every live cell dies and a domino spawns two new cells
+        let mut grid = grid_from_points(6, 6, &[(2, 2), (3, 2)]);
+        grid.rule = "B2/S".parse().unwrap();
+        grid.step();
+        assert_eq!(alive_cells(&grid), vec![(2, 1), (2, 3), (3, 1), (3, 3)]);
+    }
+
+    #[test]
+    fn highlife_births_on_six() {
+        // A cell with exactly six neighbors is born under B36/S23 but not under B3/S23
+        let ring = [(1, 1), (2, 1), (3, 1), (1, 3), (2, 3), (3, 3)];
+        let mut life = grid_from_points(6, 6, &ring);
+        life.step();
+        assert_eq!(life.get(2, 2), 0);
+
+        let mut highlife = grid_from_points(6, 6, &ring);
+        highlife.rule = "B36/S23".parse().unwrap();
+        highlife.step();
+        assert_eq!(highlife.get(2, 2), 1);
+    }
+
+    #[test]
+    fn packed_step_matches_scalar_step() {
+        // Odd sizes exercise partial words and rows spanning several words
+        // (128, 64) divides evenly into tiles, which lets a torus skip its edges
+        let rules = ["B3/S23", "B36/S23", "B2/S", "B3678/S34678", "B3/S23"];
+        let sizes = [(5, 5), (64, 9), (70, 33), (130, 67), (128, 64)];
+        for (i, &(width, height)) in sizes.iter().enumerate() {
+            for &topology in crate::topology::ALL {
+                let mut packed = Grid::new(width, height);
+                packed.topology = topology;
+                packed.rule = rules[i].parse().unwrap();
+                let soup = packed.bounds().unwrap();
+                packed.randomize(soup, 0.4, &mut fastrand::Rng::with_seed(i as u64));
+                let mut scalar = Grid::new(width, height);
+                scalar.topology = topology;
+                scalar.rule = packed.rule;
+                for (x, y, age) in packed.live_cells() {
+                    scalar.set(x, y, age);
+                }
+
+                for _ in 0..60 {
+                    packed.step();
+                    scalar.step_scalar();
+                    assert_eq!(
+                        packed.ages, scalar.ages,
+                        "{}x{} {}",
+                        width, height, topology
+                    );
+                    assert_eq!(packed.bits, scalar.bits);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn threaded_step_matches_serial_step() {
+        // Uneven band sizes, with bands meeting across the wrapped top and bottom edges
+        for &(width, height, threads) in &[(100, 97, 4), (200, 130, 7), (64, 64, 3)] {
+            for &topology in crate::topology::ALL {
+                let mut serial = Grid::new(width, height);
+                serial.topology = topology;
+                serial.threads = 1;
+                let soup = serial.bounds().unwrap();
+                serial.randomize(soup, 0.35, &mut fastrand::Rng::with_seed(threads as u64));
+                let mut threaded = Grid::new(width, height);
+                threaded.topology = topology;
+                threaded.threads = threads;
+                for (x, y, age) in serial.live_cells() {
+                    threaded.set(x, y, age);
+                }
+
+                for generation in 0..40 {
+                    // Ages come and go mid-run to cover both band paths
+                    if generation == 20 {
+                        serial.set_track_ages(false);
+                        threaded.set_track_ages(false);
+                    }
+                    serial.step();
+                    threaded.step();
+                    assert_eq!(
+                        threaded.bits, serial.bits,
+                        "{}x{} {}",
+                        width, height, topology
+                    );
+                    assert_eq!(threaded.ages, serial.ages);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn static_tiles_are_skipped() {
+        // A block far from a blinker settles into tiles that are no longer computed
+        let mut grid = grid_from_points(256, 128, &[(130, 60), (131, 60), (130, 61), (131, 61)]);
+        grid.threads = 1;
+        for (x, y) in [(70, 20), (70, 21), (70, 22)] {
+            grid.set(x, y, 1);
+        }
+        grid.step();
+        grid.step();
+        assert!(grid.active_at(70, 21));
+        assert!(!grid.active_at(130, 60));
+        assert!(!grid.active_at(150, 90));
+        assert_eq!(grid.get(130, 60), 3);
+
+        // Editing a cell wakes its tile up again
+        grid.set(132, 60, 1);
+        grid.step();
+        assert!(grid.active_at(130, 60));
+
+        // A new rule recomputes everything
+        grid.rule = "B36/S23".parse().unwrap();
+        grid.step();
+        assert!(grid.active_at(150, 90));
+
+        // So does a new topology: a block across the seam of a torus falls
+        // apart on a plane
+        let mut grid = grid_from_points(128, 64, &[(127, 10), (0, 10), (127, 11), (0, 11)]);
+        grid.threads = 1;
+        grid.step();
+        grid.step();
+        assert_eq!(grid.population(), 4);
+        grid.set_topology(Topology::Plane);
+        grid.step();
+        assert_eq!(grid.population(), 0);
+    }
+
+    #[test]
+    fn ages_restart_when_tracking_resumes() {
+        let mut grid = grid_from_points(6, 6, &[(1, 1), (2, 1), (1, 2), (2, 2)]);
+        grid.step();
+        assert_eq!(grid.get(1, 1), 2);
+        grid.set_track_ages(false);
+        grid.step();
+        assert_eq!(grid.get(1, 1), 1);
+        grid.set_track_ages(true);
+        grid.step();
+        assert_eq!(grid.get(1, 1), 2);
+    }
 }
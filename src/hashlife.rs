@@ -0,0 +1,521 @@
+use std::collections::HashMap;
+
+use crate::rule::Rule;
+use crate::topology::Topology;
+use crate::universe::{Engine, MAX_COORD, Region, Universe};
+
+type NodeId = u32;
+
+const DEAD: NodeId = 0;
+const ALIVE: NodeId = 1;
+/// Level of the root of an empty universe (an 8×8 square).
+const MIN_LEVEL: u8 = 3;
+/// Node count above which unreachable nodes and cached results are dropped.
+const GC_THRESHOLD: usize = 1 << 21;
+
+/// A square of `2^level` cells, split into four quadrants of the level below.
+/// Level 0 nodes are single cells.
+#[derive(Clone, Copy)]
+struct Node {
+    /// Quadrants in `[nw, ne, sw, se]` order.
+    children: [NodeId; 4],
+    level: u8,
+    population: u64,
+}
+
+/// Unbounded universe stored as a quadtree of shared, hash-consed nodes. Each node
+/// memoizes its center advanced by a power of two generations, so repetitive
+/// patterns can be jumped millions of generations ahead. Cell ages are not kept:
+/// every live cell reads as age 1.
+pub struct HashLife {
+    nodes: Vec<Node>,
+    index: HashMap<[NodeId; 4], NodeId>,
+    /// `(node, j)` → center of `node` after `2^j` generations.
+    results: HashMap<(NodeId, u8), NodeId>,
+    /// The empty node of each level.
+    empty: Vec<NodeId>,
+    /// Covers `-2^(level-1)..2^(level-1)` on both axes.
+    root: NodeId,
+    rule: Rule,
+}
+
+impl HashLife {
+    pub fn new() -> Self {
+        let leaf = |population| Node {
+            children: [DEAD; 4],
+            level: 0,
+            population,
+        };
+        let mut life = Self {
+            nodes: vec![leaf(0), leaf(1)],
+            index: HashMap::new(),
+            results: HashMap::new(),
+            empty: vec![DEAD],
+            root: DEAD,
+            rule: Rule::default(),
+        };
+        life.root = life.empty(MIN_LEVEL);
+        life
+    }
+
+    fn node(&self, id: NodeId) -> Node {
+        self.nodes[id as usize]
+    }
+
+    fn level(&self) -> u8 {
+        self.node(self.root).level
+    }
+
+    /// The canonical node with the given quadrants.
+    fn join(&mut self, children: [NodeId; 4]) -> NodeId {
+        if let Some(&id) = self.index.get(&children) {
+            return id;
+        }
+        let id = self.nodes.len() as NodeId;
+        self.nodes.push(Node {
+            children,
+            level: self.node(children[0]).level + 1,
+            population: children
+                .iter()
+                .fold(0u64, |sum, &c| sum.saturating_add(self.node(c).population)),
+        });
+        self.index.insert(children, id);
+        id
+    }
+
+    fn empty(&mut self, level: u8) -> NodeId {
+        while self.empty.len() <= level as usize {
+            let below = *self.empty.last().unwrap();
+            let node = self.join([below; 4]);
+            self.empty.push(node);
+        }
+        self.empty[level as usize]
+    }
+
+    /// Doubles the root's size, keeping its contents centered.
+    fn expand(&mut self) {
+        let [nw, ne, sw, se] = self.node(self.root).children;
+        let e = self.empty(self.level() - 1);
+        let children = [
+            self.join([e, e, e, nw]),
+            self.join([e, e, ne, e]),
+            self.join([e, sw, e, e]),
+            self.join([se, e, e, e]),
+        ];
+        self.root = self.join(children);
+    }
+
+    /// Whether every live cell lies within the central half of the root.
+    fn is_padded(&self) -> bool {
+        let [nw, ne, sw, se] = self.node(self.root).children;
+        // The one grandchild of each quadrant that touches the center may be populated
+        [(nw, 3), (ne, 2), (sw, 1), (se, 0)]
+            .iter()
+            .all(|&(quadrant, inner)| {
+                let children = self.node(quadrant).children;
+                (0..4).all(|i| i == inner || self.node(children[i]).population == 0)
+            })
+    }
+
+    fn contains(&self, x: isize, y: isize) -> bool {
+        let half = 1isize << (self.level() - 1);
+        (-half..half).contains(&x) && (-half..half).contains(&y)
+    }
+
+    fn set_in(&mut self, id: NodeId, x: usize, y: usize, alive: bool) -> NodeId {
+        let node = self.node(id);
+        if node.level == 0 {
+            return if alive { ALIVE } else { DEAD };
+        }
+        let half = 1 << (node.level - 1);
+        let quadrant = (y >= half) as usize * 2 + (x >= half) as usize;
+        let mut children = node.children;
+        children[quadrant] = self.set_in(children[quadrant], x % half, y % half, alive);
+        self.join(children)
+    }
+
+    /// Advances the 2×2 center of a 4×4 node by one generation.
+    fn step_4x4(&mut self, id: NodeId) -> NodeId {
+        let mut alive = [[false; 4]; 4];
+        for (quadrant, &child) in self.node(id).children.iter().enumerate() {
+            for (cell, &leaf) in self.node(child).children.iter().enumerate() {
+                let x = (quadrant % 2) * 2 + cell % 2;
+                let y = (quadrant / 2) * 2 + cell / 2;
+                alive[y][x] = leaf == ALIVE;
+            }
+        }
+
+        let mut next = [DEAD; 4];
+        for (i, cell) in next.iter_mut().enumerate() {
+            let (x, y) = (1 + i % 2, 1 + i / 2);
+            let block = alive[y - 1..=y + 1]
+                .iter()
+                .flat_map(|row| &row[x - 1..=x + 1])
+                .filter(|&&a| a)
+                .count() as u8;
+            let neighbors = block - alive[y][x] as u8;
+            let lives = if alive[y][x] {
+                self.rule.survives(neighbors)
+            } else {
+                self.rule.born(neighbors)
+            };
+            *cell = if lives { ALIVE } else { DEAD };
+        }
+        self.join(next)
+    }
+
+    /// The center half of node `id`, advanced `2^j` generations (`j` is capped at
+    /// `level - 2`, the furthest the node's contents determine).
+    fn successor(&mut self, id: NodeId, j: u8) -> NodeId {
+        let node = self.node(id);
+        let j = j.min(node.level - 2);
+        if node.population == 0 {
+            return self.empty(node.level - 1);
+        }
+        if let Some(&result) = self.results.get(&(id, j)) {
+            return result;
+        }
+
+        let result = if node.level == 2 {
+            self.step_4x4(id)
+        } else {
+            let [a, b, c, d] = node.children;
+            let [_, ab, ac, ad] = self.node(a).children;
+            let [ba, _, bc, bd] = self.node(b).children;
+            let [ca, cb, _, cd] = self.node(c).children;
+            let [da, db, dc, _] = self.node(d).children;
+
+            // Nine overlapping sub-squares, each advanced (at most) half way
+            let n2 = self.join([ab, ba, ad, bc]);
+            let n4 = self.join([ac, ad, ca, cb]);
+            let n5 = self.join([ad, bc, cb, da]);
+            let n6 = self.join([bc, bd, da, db]);
+            let n8 = self.join([cb, da, cd, dc]);
+            let mut c = [a, n2, b, n4, n5, n6, c, n8, d];
+            for n in c.iter_mut() {
+                *n = self.successor(*n, j);
+            }
+
+            let groups = [[0, 1, 3, 4], [1, 2, 4, 5], [3, 4, 6, 7], [4, 5, 7, 8]];
+            let mut quadrants = [DEAD; 4];
+            for (quadrant, group) in quadrants.iter_mut().zip(groups) {
+                *quadrant = if j < node.level - 2 {
+                    // The first half already covered all 2^j generations; just recenter
+                    let inner = [
+                        self.node(c[group[0]]).children[3],
+                        self.node(c[group[1]]).children[2],
+                        self.node(c[group[2]]).children[1],
+                        self.node(c[group[3]]).children[0],
+                    ];
+                    self.join(inner)
+                } else {
+                    let joined = self.join(group.map(|i| c[i]));
+                    self.successor(joined, j)
+                };
+            }
+            self.join(quadrants)
+        };
+        self.results.insert((id, j), result);
+        result
+    }
+
+    /// Advances the whole universe by `2^j` generations.
+    fn advance(&mut self, j: u8) {
+        if self.node(self.root).population == 0 {
+            return;
+        }
+        // Pad so nothing can travel past the edge of the result in 2^j generations
+        while self.level() < j + 2 || !self.is_padded() {
+            self.expand();
+        }
+        self.expand();
+        self.root = self.successor(self.root, j);
+        if self.nodes.len() > GC_THRESHOLD {
+            self.collect_garbage();
+        }
+    }
+
+    /// Rebuilds the node table with only the nodes reachable from the root.
+    fn collect_garbage(&mut self) {
+        let old = std::mem::take(self);
+        self.rule = old.rule;
+        let mut remap = HashMap::from([(DEAD, DEAD), (ALIVE, ALIVE)]);
+        self.root = self.copy_from(&old, old.root, &mut remap);
+    }
+
+    fn copy_from(
+        &mut self,
+        old: &HashLife,
+        id: NodeId,
+        remap: &mut HashMap<NodeId, NodeId>,
+    ) -> NodeId {
+        if let Some(&new) = remap.get(&id) {
+            return new;
+        }
+        let children = old.node(id).children.map(|c| self.copy_from(old, c, remap));
+        let new = self.join(children);
+        remap.insert(id, new);
+        new
+    }
+
+    fn collect_live(&self, id: NodeId, x: isize, y: isize, cells: &mut Vec<(isize, isize, u16)>) {
+        let node = self.node(id);
+        if node.population == 0 {
+            return;
+        }
+        if node.level == 0 {
+            cells.push((x, y, 1));
+            return;
+        }
+        let half = 1isize << (node.level - 1);
+        for (i, &child) in node.children.iter().enumerate() {
+            let (dx, dy) = ((i % 2) as isize * half, (i / 2) as isize * half);
+            self.collect_live(child, x + dx, y + dy, cells);
+        }
+    }
+
+    /// Grows `bounds` (min x, min y, max x, max y) to cover the live cells of a
+    /// node at `(x, y)`, skipping nodes that already lie inside it.
+    fn extend_bounds(
+        &self,
+        id: NodeId,
+        x: isize,
+        y: isize,
+        bounds: &mut Option<(isize, isize, isize, isize)>,
+    ) {
+        let node = self.node(id);
+        if node.population == 0 {
+            return;
+        }
+        let last = (1isize << node.level) - 1;
+        if let Some((x0, y0, x1, y1)) = *bounds
+            && x >= x0
+            && y >= y0
+            && x + last <= x1
+            && y + last <= y1
+        {
+            return;
+        }
+        if node.level == 0 {
+            *bounds = Some(match *bounds {
+                Some((x0, y0, x1, y1)) => (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+                None => (x, y, x, y),
+            });
+            return;
+        }
+        let half = 1isize << (node.level - 1);
+        for (i, &child) in node.children.iter().enumerate() {
+            let (dx, dy) = ((i % 2) as isize * half, (i / 2) as isize * half);
+            self.extend_bounds(child, x + dx, y + dy, bounds);
+        }
+    }
+}
+
+impl Default for HashLife {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Universe for HashLife {
+    fn get(&self, x: isize, y: isize) -> u16 {
+        if !self.contains(x, y) {
+            return 0;
+        }
+        let half = 1isize << (self.level() - 1);
+        let (mut x, mut y) = ((x + half) as usize, (y + half) as usize);
+        let mut id = self.root;
+        loop {
+            let node = self.node(id);
+            if node.population == 0 {
+                return 0;
+            }
+            if node.level == 0 {
+                return 1;
+            }
+            let half = 1 << (node.level - 1);
+            id = node.children[(y >= half) as usize * 2 + (x >= half) as usize];
+            (x, y) = (x % half, y % half);
+        }
+    }
+
+    fn set(&mut self, x: isize, y: isize, age: u16) {
+        let in_range = |v: isize| (-MAX_COORD..MAX_COORD).contains(&v);
+        if (age == 0 && !self.contains(x, y)) || !in_range(x) || !in_range(y) {
+            return;
+        }
+        while !self.contains(x, y) {
+            self.expand();
+        }
+        let half = 1isize << (self.level() - 1);
+        let (x, y) = ((x + half) as usize, (y + half) as usize);
+        self.root = self.set_in(self.root, x, y, age > 0);
+    }
+
+    fn wrap(&self, x: isize, y: isize) -> Option<(isize, isize)> {
+        Some((x, y))
+    }
+
+    fn step(&mut self) {
+        self.advance(0);
+    }
+
+    fn step_by(&mut self, generations: u64) {
+        for j in 0..u64::BITS as u8 {
+            if generations >> j & 1 == 1 {
+                self.advance(j);
+            }
+        }
+    }
+
+    fn population(&self) -> usize {
+        self.node(self.root).population as usize
+    }
+
+    fn clear(&mut self) {
+        self.root = self.empty(MIN_LEVEL);
+    }
+
+    fn live_cells(&self) -> Vec<(isize, isize, u16)> {
+        let half = 1isize << (self.level() - 1);
+        let mut cells = Vec::new();
+        self.collect_live(self.root, -half, -half, &mut cells);
+        cells
+    }
+
+    fn bounding_box(&self) -> Option<Region> {
+        let half = 1isize << (self.level() - 1);
+        let mut bounds = None;
+        self.extend_bounds(self.root, -half, -half, &mut bounds);
+        bounds.map(|(x0, y0, x1, y1)| Region {
+            x: x0,
+            y: y0,
+            width: (x1 - x0 + 1) as usize,
+            height: (y1 - y0 + 1) as usize,
+        })
+    }
+
+    fn bounds(&self) -> Option<Region> {
+        None
+    }
+
+    fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    fn set_rule(&mut self, rule: Rule) -> Result<(), String> {
+        if rule.born(0) {
+            return Err(format!(
+                "{} fills empty space and cannot run unbounded",
+                rule
+            ));
+        }
+        if rule != self.rule {
+            self.results.clear();
+        }
+        self.rule = rule;
+        Ok(())
+    }
+
+    fn topology(&self) -> Option<Topology> {
+        None
+    }
+
+    fn set_topology(&mut self, _topology: Topology) {}
+
+    fn engine(&self) -> Engine {
+        Engine::HashLife
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plane::Plane;
+
+    fn sorted_cells(universe: &dyn Universe) -> Vec<(isize, isize)> {
+        let mut cells: Vec<_> = universe
+            .live_cells()
+            .into_iter()
+            .map(|(x, y, _)| (x, y))
+            .collect();
+        cells.sort();
+        cells
+    }
+
+    #[test]
+    fn matches_plane_one_generation_at_a_time() {
+        let mut life = HashLife::new();
+        let mut plane = Plane::new();
+        let soup = Region {
+            x: -12,
+            y: -12,
+            width: 24,
+            height: 24,
+        };
+        plane.randomize(soup, 0.4, &mut fastrand::Rng::with_seed(3));
+        for (x, y, _) in plane.live_cells() {
+            life.set(x, y, 1);
+        }
+
+        for _ in 0..100 {
+            life.step();
+            plane.step();
+            assert_eq!(sorted_cells(&life), sorted_cells(&plane));
+        }
+    }
+
+    #[test]
+    fn large_steps_match_single_steps() {
+        let mut fast = HashLife::new();
+        fast.set_rule("B36/S23".parse().unwrap()).unwrap();
+        let soup = Region {
+            x: 0,
+            y: 0,
+            width: 16,
+            height: 16,
+        };
+        fast.randomize(soup, 0.5, &mut fastrand::Rng::with_seed(11));
+        let mut slow = HashLife::new();
+        slow.set_rule(fast.rule()).unwrap();
+        for (x, y, _) in fast.live_cells() {
+            slow.set(x, y, 1);
+        }
+
+        fast.step_by(300);
+        for _ in 0..300 {
+            slow.step();
+        }
+        assert_eq!(sorted_cells(&fast), sorted_cells(&slow));
+        let cells = fast.live_cells().into_iter().map(|(x, y, _)| (x, y));
+        assert_eq!(fast.bounding_box(), Region::bounding(cells));
+    }
+
+    #[test]
+    fn glider_travels_a_million_generations() {
+        let mut life = HashLife::new();
+        let glider = [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)];
+        for &(x, y) in &glider {
+            life.set(x, y, 1);
+        }
+        life.step_by(1 << 20);
+        // A glider moves one cell diagonally every four generations
+        let mut expected: Vec<_> = glider
+            .iter()
+            .map(|&(x, y)| (x + (1 << 18), y + (1 << 18)))
+            .collect();
+        expected.sort();
+        assert_eq!(sorted_cells(&life), expected);
+        assert_eq!(life.get(1 + (1 << 18), 1 << 18), 1);
+    }
+
+    #[test]
+    fn ignores_cells_past_the_coordinate_limit() {
+        let mut life = HashLife::new();
+        life.set(isize::MAX, 0, 1);
+        life.set(0, -MAX_COORD - 1, 1);
+        assert_eq!(life.population(), 0);
+        life.set(MAX_COORD - 1, -MAX_COORD, 1);
+        assert_eq!(life.get(MAX_COORD - 1, -MAX_COORD), 1);
+    }
+}
@@ -0,0 +1,60 @@
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::Instant;
+
+use crate::app::App;
+use crate::cli::Args;
+use crate::export;
+use crate::history::History;
+use crate::session;
+use crate::timeline::Timeline;
+
+/// Steps the simulation without a terminal, printing a tab-separated
+/// `generation population` table to stdout and a timing summary to stderr.
+pub fn run(mut app: App, args: &Args) -> io::Result<()> {
+    let generations = args.generations.unwrap_or(0);
+    // Only sessions keep ages, and the grid steps faster without them
+    if !args.output.as_deref().is_some_and(is_session) {
+        app.universe.set_track_ages(false);
+    }
+    app.history = History::new(0);
+    app.timeline = Timeline::new(0);
+    let mut out = io::stdout().lock();
+    writeln!(out, "generation\tpopulation")?;
+    writeln!(out, "{}\t{}", app.generation, app.universe.population())?;
+
+    // Advance straight to each reported generation so HashLife can skip ahead
+    let start = Instant::now();
+    let mut done = 0;
+    while done < generations {
+        let chunk = args.every.unwrap_or(generations).min(generations - done);
+        app.advance(chunk as u64);
+        done += chunk;
+        writeln!(out, "{}\t{}", app.generation, app.universe.population())?;
+    }
+    let elapsed = start.elapsed();
+    eprintln!(
+        "{} generations in {:.2?} ({:.0} gen/s)",
+        generations,
+        elapsed,
+        generations as f64 / elapsed.as_secs_f64().max(f64::EPSILON)
+    );
+
+    if let Some(path) = &args.output {
+        write_output(&app, path)?;
+        eprintln!("wrote {}", path.display());
+    }
+    Ok(())
+}
+
+fn is_session(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "petri")
+}
+
+fn write_output(app: &App, path: &Path) -> io::Result<()> {
+    if is_session(path) {
+        session::save(app, path)
+    } else {
+        export::write_pattern(&app.grid_pattern(), path).map(|_| ())
+    }
+}
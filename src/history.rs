@@ -0,0 +1,278 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::universe::Universe;
+
+/// Total cells the undo and redo stacks may hold before the oldest entries are
+/// dropped.
+pub const DEFAULT_MAX_CELLS: usize = 4_000_000;
+const MAX_ENTRIES: usize = 1000;
+
+type Cell = (isize, isize, u16);
+
+/// One undoable action.
+enum Change {
+    /// Cells an edit touched, with their ages before and after.
+    Edit(Vec<(isize, isize, u16, u16)>),
+    /// Every live cell and the generation counter on the other side of a step.
+    /// Steps age every live cell, so a full snapshot is no bigger than a diff.
+    Step { cells: Vec<Cell>, generation: usize },
+}
+
+impl Change {
+    fn size(&self) -> usize {
+        match self {
+            Change::Edit(cells) => cells.len(),
+            Change::Step { cells, .. } => cells.len(),
+        }
+    }
+}
+
+/// Bounded undo/redo stacks covering edits and simulation steps.
+pub struct History {
+    undo: VecDeque<Change>,
+    redo: Vec<Change>,
+    /// Cells held across both stacks.
+    size: usize,
+    max_cells: usize,
+    /// Whether the newest entry is the start of a run still going on.
+    in_run: bool,
+}
+
+impl History {
+    /// A history holding at most `max_cells` cells; 0 disables it.
+    pub fn new(max_cells: usize) -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            size: 0,
+            max_cells,
+            in_run: false,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.max_cells > 0
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.size = 0;
+        self.in_run = false;
+    }
+
+    /// Makes the next step start a new entry even while running.
+    pub fn end_run(&mut self) {
+        self.in_run = false;
+    }
+
+    fn push(&mut self, change: Change) {
+        self.in_run = false;
+        for dropped in self.redo.drain(..) {
+            self.size -= dropped.size();
+        }
+        self.size += change.size();
+        self.undo.push_back(change);
+        while self.size > self.max_cells || self.undo.len() > MAX_ENTRIES {
+            let Some(oldest) = self.undo.pop_front() else {
+                break;
+            };
+            self.size -= oldest.size();
+        }
+    }
+
+    /// Records an edit from the live cells before and after it.
+    pub fn record_edit(&mut self, before: &[Cell], after: &[Cell]) {
+        if !self.is_enabled() {
+            return;
+        }
+        let mut ages: HashMap<(isize, isize), (u16, u16)> = before
+            .iter()
+            .map(|&(x, y, age)| ((x, y), (age, 0)))
+            .collect();
+        for &(x, y, age) in after {
+            ages.entry((x, y)).or_insert((0, 0)).1 = age;
+        }
+        let diff: Vec<_> = ages
+            .into_iter()
+            .filter(|(_, (old, new))| old != new)
+            .map(|((x, y), (old, new))| (x, y, old, new))
+            .collect();
+        if !diff.is_empty() {
+            self.push(Change::Edit(diff));
+        }
+    }
+
+    /// Records the state a step is about to leave behind. A state too big to
+    /// hold empties the history, since nothing before it could be reached.
+    /// Steps taken while `running` after the first add nothing, so a whole run
+    /// costs one snapshot and is undone at once.
+    pub fn record_step(&mut self, universe: &dyn Universe, generation: usize, running: bool) {
+        if !self.is_enabled() || (running && self.in_run) {
+            return;
+        }
+        if universe.population() > self.max_cells {
+            self.clear();
+            return;
+        }
+        self.push(Change::Step {
+            cells: universe.live_cells(),
+            generation,
+        });
+        self.in_run = running;
+    }
+
+    /// Reverts the last change, returning a description of it.
+    pub fn undo(&mut self, universe: &mut dyn Universe, generation: &mut usize) -> Option<&str> {
+        let change = self.undo.pop_back()?;
+        self.in_run = false;
+        self.size -= change.size();
+        let (inverse, label) = apply(change, universe, generation, false);
+        self.size += inverse.size();
+        self.redo.push(inverse);
+        Some(label)
+    }
+
+    /// Reapplies the last undone change, returning a description of it.
+    pub fn redo(&mut self, universe: &mut dyn Universe, generation: &mut usize) -> Option<&str> {
+        let change = self.redo.pop()?;
+        self.in_run = false;
+        self.size -= change.size();
+        let (inverse, label) = apply(change, universe, generation, true);
+        self.size += inverse.size();
+        self.undo.push_back(inverse);
+        Some(label)
+    }
+}
+
+/// Applies one side of a change (`forward` for the "after" ages of an edit) and
+/// returns the change that takes it back.
+fn apply(
+    change: Change,
+    universe: &mut dyn Universe,
+    generation: &mut usize,
+    forward: bool,
+) -> (Change, &'static str) {
+    match change {
+        Change::Edit(cells) => {
+            for &(x, y, old, new) in &cells {
+                universe.set(x, y, if forward { new } else { old });
+            }
+            (Change::Edit(cells), "edit")
+        }
+        Change::Step {
+            cells,
+            generation: target,
+        } => {
+            let inverse = Change::Step {
+                cells: universe.live_cells(),
+                generation: *generation,
+            };
+            universe.clear();
+            for (x, y, age) in cells {
+                universe.set(x, y, age);
+            }
+            *generation = target;
+            (inverse, "step")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Grid;
+
+    #[test]
+    fn undoes_and_redoes_edits_and_steps() {
+        let mut grid = Grid::new(8, 8);
+        let mut history = History::new(DEFAULT_MAX_CELLS);
+        let mut generation = 0;
+
+        let before = grid.live_cells();
+        for (x, y) in [(1, 2), (2, 2), (3, 2)] {
+            grid.set(x, y, 1);
+        }
+        history.record_edit(&before, &grid.live_cells());
+        let blinker = grid.live_cells();
+
+        history.record_step(&grid, generation, false);
+        grid.step();
+        generation += 1;
+        let stepped = grid.live_cells();
+
+        assert_eq!(history.undo(&mut grid, &mut generation), Some("step"));
+        assert_eq!((grid.live_cells(), generation), (blinker.clone(), 0));
+        assert_eq!(history.undo(&mut grid, &mut generation), Some("edit"));
+        assert_eq!(grid.population(), 0);
+        assert_eq!(history.undo(&mut grid, &mut generation), None);
+
+        assert_eq!(history.redo(&mut grid, &mut generation), Some("edit"));
+        assert_eq!(grid.live_cells(), blinker);
+        assert_eq!(history.redo(&mut grid, &mut generation), Some("step"));
+        assert_eq!((grid.live_cells(), generation), (stepped, 1));
+        assert_eq!(history.redo(&mut grid, &mut generation), None);
+    }
+
+    #[test]
+    fn editing_after_undoing_a_growing_step_keeps_the_count() {
+        let mut grid = Grid::new(16, 16);
+        let mut history = History::new(DEFAULT_MAX_CELLS);
+        let mut generation = 0;
+        // An R-pentomino grows on its first step
+        for (x, y) in [(6, 5), (7, 5), (5, 6), (6, 6), (6, 7)] {
+            grid.set(x, y, 1);
+        }
+        history.record_step(&grid, generation, false);
+        grid.step();
+        generation += 1;
+        history.undo(&mut grid, &mut generation);
+        // The redo stack now holds the six cells after the step
+        assert_eq!(history.size, 6);
+
+        let before = grid.live_cells();
+        grid.set(0, 0, 1);
+        history.record_edit(&before, &grid.live_cells());
+        assert_eq!(history.size, 1);
+    }
+
+    #[test]
+    fn a_run_is_undone_at_once() {
+        let mut grid = Grid::new(8, 8);
+        let mut history = History::new(DEFAULT_MAX_CELLS);
+        let mut generation = 0;
+        for (x, y) in [(1, 2), (2, 2), (3, 2)] {
+            grid.set(x, y, 1);
+        }
+        let start = grid.live_cells();
+        for _ in 0..5 {
+            history.record_step(&grid, generation, true);
+            grid.step();
+            generation += 1;
+        }
+        assert_eq!(history.size, 3);
+        assert_eq!(history.undo(&mut grid, &mut generation), Some("step"));
+        assert_eq!((grid.live_cells(), generation), (start, 0));
+        assert_eq!(history.undo(&mut grid, &mut generation), None);
+    }
+
+    #[test]
+    fn drops_oldest_changes_past_the_limit() {
+        let mut grid = Grid::new(8, 8);
+        let mut history = History::new(10);
+        for i in 0..8 {
+            let before = grid.live_cells();
+            grid.set(i, 0, 1);
+            grid.set(i, 1, 1);
+            history.record_edit(&before, &grid.live_cells());
+        }
+        // Two cells per edit, so only the last five fit
+        let mut generation = 0;
+        let mut undone = 0;
+        while history.undo(&mut grid, &mut generation).is_some() {
+            undone += 1;
+        }
+        assert_eq!(undone, 5);
+        assert_eq!(grid.population(), 6);
+    }
+}
@@ -0,0 +1,298 @@
+//! The pattern library panel: built-in patterns plus every pattern file in a
+//! user directory, filtered by a fuzzy search.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::patterns::{self, Format, Pattern};
+
+/// Words in pattern names and comments that place a pattern in a category.
+const CATEGORIES: &[(&str, &str)] = &[
+    ("still life", "still life"),
+    ("oscillator", "oscillator"),
+    ("gun", "gun"),
+    ("spaceship", "spaceship"),
+    ("glider", "spaceship"),
+    ("puffer", "puffer"),
+    ("rake", "rake"),
+    ("breeder", "breeder"),
+    ("methuselah", "methuselah"),
+    ("pulsar", "oscillator"),
+];
+
+/// Periods of the built-in patterns, which carry no comments.
+const BUILTIN_PERIODS: &[(&str, usize)] = &[("glider", 4), ("pulsar", 3), ("glider gun", 30)];
+
+pub struct Entry {
+    pub pattern: Pattern,
+    pub category: &'static str,
+    pub period: Option<usize>,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Entry {
+    pub fn new(pattern: Pattern) -> Self {
+        let text = pattern.comments.join(" ").to_lowercase();
+        let name = pattern.name.to_lowercase();
+        let category = CATEGORIES
+            .iter()
+            .find(|(word, _)| name.contains(word) || text.contains(word))
+            .map_or("other", |&(_, category)| category);
+        let period = BUILTIN_PERIODS
+            .iter()
+            .find(|&&(builtin, _)| pattern.comments.is_empty() && builtin == name)
+            .map(|&(_, period)| period)
+            .or_else(|| parse_period(&text));
+        let (width, height) = match patterns::bounding_box(&pattern.cells) {
+            Some((x0, y0, x1, y1)) => ((x1 - x0 + 1) as usize, (y1 - y0 + 1) as usize),
+            None => (0, 0),
+        };
+        Self {
+            pattern,
+            category,
+            period,
+            width,
+            height,
+        }
+    }
+}
+
+/// Finds "period 30", "period-30" or "p30" in lowercase comment text.
+fn parse_period(text: &str) -> Option<usize> {
+    let words: Vec<&str> = text
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect();
+    words.iter().enumerate().find_map(|(i, word)| {
+        if *word == "period" {
+            words.get(i + 1)?.parse().ok()
+        } else {
+            word.strip_prefix('p')?.parse().ok()
+        }
+    })
+}
+
+/// `$XDG_CONFIG_HOME/petri/patterns`, falling back to `~/.config/petri/patterns`.
+pub fn default_dir() -> Option<PathBuf> {
+    let config = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(config.join("petri").join("patterns"))
+}
+
+/// Subsequence match of `query` in `name`, ignoring case. Higher scores mean
+/// closer matches: consecutive letters and matches at word starts count most.
+pub fn fuzzy_score(query: &str, name: &str) -> Option<usize> {
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut next = 0;
+    let mut last = None;
+    for q in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let i = next + name[next..].iter().position(|&c| c == q)?;
+        score += 1;
+        if last.is_some_and(|l| l + 1 == i) {
+            score += 4;
+        }
+        if i == 0 || !name[i - 1].is_alphanumeric() {
+            score += 2;
+        }
+        last = Some(i);
+        next = i + 1;
+    }
+    // Prefer shorter names among equal matches
+    Some(score * 256 + 255 - name.len().min(255))
+}
+
+pub struct Library {
+    pub entries: Vec<Entry>,
+    pub query: String,
+    /// Indices into `entries` matching the query, best first.
+    pub matches: Vec<usize>,
+    /// Position of the highlighted entry within `matches`.
+    pub selected: usize,
+    /// Pattern files in the user directory that could not be read.
+    pub errors: usize,
+}
+
+impl Library {
+    pub fn new(patterns: Vec<Pattern>) -> Self {
+        let mut library = Self {
+            entries: patterns.into_iter().map(Entry::new).collect(),
+            query: String::new(),
+            matches: Vec::new(),
+            selected: 0,
+            errors: 0,
+        };
+        library.refilter();
+        library
+    }
+
+    /// The built-in patterns followed by every readable pattern file in `dir`,
+    /// sorted by name.
+    pub fn load(dir: &Path) -> io::Result<Self> {
+        let mut found = Vec::new();
+        let mut errors = 0;
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_file() && Format::from_path(&path).is_some() {
+                match patterns::load(&path) {
+                    Ok(pattern) => found.push(pattern),
+                    Err(_) => errors += 1,
+                }
+            }
+        }
+        found.sort_by_key(|p| p.name.to_lowercase());
+        let mut all: Vec<Pattern> = patterns::ALL.iter().map(|&p| p.clone()).collect();
+        all.extend(found);
+        let mut library = Self::new(all);
+        library.errors = errors;
+        Ok(library)
+    }
+
+    fn refilter(&mut self) {
+        let mut scored: Vec<(usize, usize)> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(i, e)| {
+                let name = fuzzy_score(&self.query, &e.pattern.name);
+                let category = fuzzy_score(&self.query, e.category).map(|s| s / 2);
+                Some((i, name.max(category)?))
+            })
+            .collect();
+        if !self.query.is_empty() {
+            scored.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        }
+        self.matches = scored.into_iter().map(|(i, _)| i).collect();
+        self.selected = 0;
+    }
+
+    pub fn push(&mut self, c: char) {
+        self.query.push(c);
+        self.refilter();
+    }
+
+    pub fn backspace(&mut self) {
+        self.query.pop();
+        self.refilter();
+    }
+
+    pub fn move_selection(&mut self, delta: isize) {
+        let last = self.matches.len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(last);
+    }
+
+    pub fn selected_entry(&self) -> Option<&Entry> {
+        self.entries.get(*self.matches.get(self.selected)?)
+    }
+}
+
+/// Renders `pattern` with half blocks into at most `cols` x `rows` characters,
+/// shrinking it when it does not fit; a character is lit if any cell it covers is.
+pub fn thumbnail(pattern: &Pattern, cols: usize, rows: usize) -> Vec<String> {
+    let Some((x0, y0, x1, y1)) = patterns::bounding_box(&pattern.cells) else {
+        return Vec::new();
+    };
+    let (width, height) = ((x1 - x0 + 1) as usize, (y1 - y0 + 1) as usize);
+    let scale = width
+        .div_ceil(cols.max(1))
+        .max(height.div_ceil(rows.max(1) * 2))
+        .max(1);
+    let (w, h) = (width.div_ceil(scale), height.div_ceil(scale));
+    let mut lit = vec![false; w * h];
+    for &(x, y) in pattern.cells.iter() {
+        let (x, y) = ((x - x0) as usize / scale, (y - y0) as usize / scale);
+        lit[y * w + x] = true;
+    }
+    (0..h)
+        .step_by(2)
+        .map(|y| {
+            (0..w)
+                .map(|x| {
+                    let top = lit[y * w + x];
+                    let bottom = y + 1 < h && lit[(y + 1) * w + x];
+                    match (top, bottom) {
+                        (true, true) => '█',
+                        (true, false) => '▀',
+                        (false, true) => '▄',
+                        (false, false) => ' ',
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_metadata_from_comments() {
+        let mut pattern = Pattern::from_cells("weekender".to_string(), vec![(0, 0)]);
+        pattern.comments = vec!["An orthogonal c/7 spaceship with period 7.".to_string()];
+        let entry = Entry::new(pattern);
+        assert_eq!((entry.category, entry.period), ("spaceship", Some(7)));
+
+        let mut pattern = Pattern::from_cells("thing".to_string(), vec![(0, 0), (3, 1)]);
+        pattern.comments = vec!["A p46 shuttle".to_string()];
+        let entry = Entry::new(pattern);
+        assert_eq!((entry.category, entry.period), ("other", Some(46)));
+        assert_eq!((entry.width, entry.height), (4, 2));
+
+        let gun = Entry::new(patterns::GLIDER_GUN.clone());
+        assert_eq!((gun.category, gun.period), ("gun", Some(30)));
+    }
+
+    #[test]
+    fn fuzzy_search_ranks_closer_matches_first() {
+        assert!(fuzzy_score("ggun", "glider gun").is_some());
+        assert!(fuzzy_score("gnu", "glider gun").is_none());
+        assert!(fuzzy_score("gun", "glider gun") > fuzzy_score("gun", "gaussian"));
+
+        let mut library = Library::new(patterns::ALL.iter().map(|&p| p.clone()).collect());
+        for c in "gun".chars() {
+            library.push(c);
+        }
+        assert_eq!(library.matches.len(), 1);
+        assert_eq!(library.selected_entry().unwrap().pattern.name, "glider gun");
+        library.backspace();
+        library.backspace();
+        assert_eq!(library.selected_entry().unwrap().pattern.name, "glider");
+        library.query.clear();
+        library.refilter();
+        assert_eq!(library.matches.len(), 3);
+    }
+
+    #[test]
+    fn loads_a_pattern_directory() {
+        let dir = env::temp_dir().join(format!("petri-library-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("blinker.rle"),
+            "#N Blinker\n#C A period 2 oscillator.\nx = 3, y = 1\n3o!\n",
+        )
+        .unwrap();
+        fs::write(dir.join("broken.cells"), "!Name: broken\nxyz\n").unwrap();
+        fs::write(dir.join("notes.txt"), "not a pattern").unwrap();
+        let library = Library::load(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(library.entries.len(), patterns::ALL.len() + 1);
+        assert_eq!(library.errors, 1);
+        let blinker = library.entries.last().unwrap();
+        assert_eq!(blinker.pattern.name, "Blinker");
+        assert_eq!((blinker.category, blinker.period), ("oscillator", Some(2)));
+        assert_eq!((blinker.width, blinker.height), (3, 1));
+    }
+
+    #[test]
+    fn thumbnails_shrink_to_fit() {
+        assert_eq!(thumbnail(&patterns::GLIDER, 10, 10), vec![" ▀▄", "▀▀▀"]);
+        let gun = thumbnail(&patterns::GLIDER_GUN, 12, 2);
+        assert!(gun.len() <= 2 && gun.iter().all(|row| row.chars().count() <= 12));
+    }
+}
@@ -1,11 +1,27 @@
 mod app;
+mod bench;
+mod cli;
 mod export;
 mod grid;
+mod hashlife;
+mod headless;
+mod history;
+mod library;
+mod minimap;
 mod patterns;
+mod plane;
+mod rule;
+mod session;
+mod timeline;
+mod topology;
 mod ui;
+mod universe;
 
 use crossterm::{
-    event::{self, Event, KeyCode},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers, MouseButton,
+        MouseEvent, MouseEventKind,
+    },
     execute,
     terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -14,68 +30,188 @@ use std::{io, time::Instant};
 use ratatui::prelude::*;
 
 fn main() -> io::Result<()> {
+    let args = match cli::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("petri: {}\n\n{}", e, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+    if args.help {
+        print!("{}", cli::USAGE);
+        return Ok(());
+    }
+    if args.bench {
+        return bench::run(&args);
+    }
+
+    let (view_width, view_height) = if args.headless {
+        (0, 0)
+    } else {
+        let (cols, rows) = crossterm::terminal::size()?;
+        app::App::view_size(cols, rows)
+    };
+    let app = match cli::build_app(&args, view_width, view_height) {
+        Ok(app) => app,
+        Err(e) => {
+            eprintln!("petri: {}", e);
+            std::process::exit(1);
+        }
+    };
+    if args.headless {
+        return headless::run(app, &args);
+    }
+
     // custom panic because the terminal remains in raw mode otherwise
     let default_panic = std::panic::take_hook();
     std::panic::set_hook(Box::new(move |info| {
         terminal::disable_raw_mode().unwrap();
-        execute!(io::stdout(), LeaveAlternateScreen).unwrap();
+        execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture).unwrap();
         default_panic(info);
     }));
 
     terminal::enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen)?;
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let result = run(&mut terminal);
+    let result = run(&mut terminal, app);
 
     terminal::disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    execute!(
+        terminal.backend_mut(),
+        LeaveAlternateScreen,
+        DisableMouseCapture
+    )?;
     result
 }
 
-fn run(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> io::Result<()> {
-    let (cols, rows) = crossterm::terminal::size()?;
-    let view_width = (cols as usize - 2) / 2;
-    let view_height = rows as usize - 3;
-
-    let mut app = app::App::new(256, 256, view_width, view_height);
+fn run(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, mut app: app::App) -> io::Result<()> {
     let mut last_tick = Instant::now();
 
     loop {
         terminal.draw(|frame| ui::draw(frame, &app))?;
 
         let timeout = app.tick_rate.saturating_sub(last_tick.elapsed());
-        if event::poll(timeout)? {
-            if let Event::Key(key) = event::read()? {
+        let event = if event::poll(timeout)? {
+            Some(event::read()?)
+        } else {
+            None
+        };
+        if let Some(Event::Resize(cols, rows)) = event {
+            app.resize(cols, rows);
+        }
+        if let Some(Event::Mouse(mouse)) = event {
+            handle_mouse(&mut app, mouse);
+        }
+        if let Some(Event::Key(key)) = event {
+            if app.prompt.is_some() {
+                match key.code {
+                    KeyCode::Enter => app.submit_prompt(),
+                    KeyCode::Esc => app.cancel_prompt(),
+                    KeyCode::Backspace => app.prompt_backspace(),
+                    KeyCode::Char(c) => app.prompt_push(c),
+                    _ => {}
+                }
+            } else if app.library_open {
+                match key.code {
+                    KeyCode::Enter => app.pick_from_library(),
+                    KeyCode::Esc => app.close_library(),
+                    KeyCode::Up => app.library.move_selection(-1),
+                    KeyCode::Down => app.library.move_selection(1),
+                    KeyCode::PageUp => app.library.move_selection(-10),
+                    KeyCode::PageDown => app.library.move_selection(10),
+                    KeyCode::Backspace => app.library.backspace(),
+                    KeyCode::Char(c) => app.library.push(c),
+                    _ => {}
+                }
+            } else {
                 match key.code {
                     KeyCode::Char('q') => return Ok(()),
                     KeyCode::Char(' ') => app.toggle_pause(),
                     KeyCode::Char('n') => app.step(),
+                    KeyCode::Char('r') if app.selection_anchor.is_some() => {
+                        app.randomize_selection()
+                    }
                     KeyCode::Char('r') => app.randomize(),
+                    KeyCode::Char('V') => app.randomize_area(app::SoupArea::Viewport),
+                    KeyCode::Char('s') => {
+                        app.randomize_area(app::SoupArea::Centered(app.soup_size))
+                    }
+                    KeyCode::Char('#') => app.open_prompt(app::PromptKind::Seed),
+                    KeyCode::Char('c') if app.selection_anchor.is_some() => app.clear_selection(),
                     KeyCode::Char('c') => app.clear(),
+                    KeyCode::Char('v') => app.toggle_selection(),
+                    KeyCode::Char('y') => app.copy_selection(),
+                    KeyCode::Char('x') => app.cut_selection(),
+                    KeyCode::Char('P') => app.paste(),
+                    KeyCode::Char('F') => app.fill_selection(),
+                    KeyCode::Char('i') => app.invert_selection(),
+                    KeyCode::Char('u') => app.undo(),
+                    KeyCode::Char('U') => app.redo(),
+                    KeyCode::Char('b') => app.scrub(-1),
+                    KeyCode::Char('f') => app.scrub(1),
+                    KeyCode::Char('{') => app.scrub_far(-1),
+                    KeyCode::Char('}') => app.scrub_far(1),
+                    KeyCode::Enter if app.placing.is_some() => app.stamp(),
                     KeyCode::Enter => app.toggle_cell(),
+                    KeyCode::Char('z') => app.reorient(patterns::Transform::rotate_clockwise),
+                    KeyCode::Char('Z') => {
+                        app.reorient(patterns::Transform::rotate_counterclockwise)
+                    }
+                    KeyCode::Char('m') => app.reorient(patterns::Transform::flip_horizontal),
+                    KeyCode::Char('M') => app.reorient(patterns::Transform::flip_vertical),
                     KeyCode::Char('h') => app.move_left(),
                     KeyCode::Char('j') => app.move_down(),
                     KeyCode::Char('k') => app.move_up(),
                     KeyCode::Char('l') => app.move_right(),
+                    // The ghost follows the arrow keys; otherwise they pan
+                    KeyCode::Left | KeyCode::Down | KeyCode::Up | KeyCode::Right
+                        if app.show_minimap && key.modifiers.contains(KeyModifiers::SHIFT) =>
+                    {
+                        match key.code {
+                            KeyCode::Left => app.nudge_view(-1, 0),
+                            KeyCode::Down => app.nudge_view(0, 1),
+                            KeyCode::Up => app.nudge_view(0, -1),
+                            _ => app.nudge_view(1, 0),
+                        }
+                    }
+                    KeyCode::Left if app.placing.is_some() => app.move_left(),
+                    KeyCode::Down if app.placing.is_some() => app.move_down(),
+                    KeyCode::Up if app.placing.is_some() => app.move_up(),
+                    KeyCode::Right if app.placing.is_some() => app.move_right(),
+                    KeyCode::Left => app.pan(-1, 0),
+                    KeyCode::Down => app.pan(0, 1),
+                    KeyCode::Up => app.pan(0, -1),
+                    KeyCode::Right => app.pan(1, 0),
+                    KeyCode::Char('E') => app.cycle_engine(),
+                    KeyCode::Char('a') => app.toggle_ages(),
+                    KeyCode::Char('D') => app.toggle_tiles(),
+                    KeyCode::Char('.') => app.double_step(),
+                    KeyCode::Char(',') => app.halve_step(),
                     KeyCode::Char('+') | KeyCode::Char('=') => app.speed_up(),
                     KeyCode::Char('-') => app.slow_down(),
                     KeyCode::Tab => app.toggle_cursor(),
+                    KeyCode::Char('R') => app.open_prompt(app::PromptKind::Rule),
+                    KeyCode::Char('t') => app.cycle_topology(),
+                    KeyCode::Char('o') => {
+                        app.cursor_visible = true;
+                        app.open_prompt(app::PromptKind::OpenPattern);
+                    }
                     KeyCode::Char(']') => app.zoom_in(),
                     KeyCode::Char('[') => app.zoom_out(),
-                    KeyCode::Char('p') => {
-                        app.pattern_mode = !app.pattern_mode;
-                        if app.pattern_mode && !app.cursor_visible {
-                            app.cursor_visible = true;
-                        }
-                    }
-                    KeyCode::Char(c) if app.pattern_mode && c.is_ascii_digit() => {
+                    KeyCode::Char('/') => app.open_library(),
+                    KeyCode::Char('C') => app.center_on_cursor(),
+                    KeyCode::Char('A') => app.fit_all(),
+                    KeyCode::Char('O') => app.toggle_minimap(),
+                    KeyCode::Char('G') => app.open_prompt(app::PromptKind::GoTo),
+                    KeyCode::Char('p') if app.placing.is_some() => app.stop_placing(),
+                    KeyCode::Char('p') => app.pick_pattern(app.last_pattern),
+                    KeyCode::Char(c) if app.placing.is_some() && c.is_ascii_digit() => {
                         let idx = c.to_digit(10).unwrap() as usize;
-                        if idx >= 1 && idx <= patterns::ALL.len() {
-                            app.place_pattern(patterns::ALL[idx - 1]);
-                            app.pattern_mode = false;
+                        if idx >= 1 {
+                            app.pick_pattern(idx - 1);
                         }
                     }
                     KeyCode::Char('g') => {
@@ -84,26 +220,33 @@ fn run(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> io::Result<()>
                             let tick_ms = app.tick_rate.as_millis() as u16;
                             match export::encode_gif(
                                 &frames,
-                                app.grid.width,
-                                app.grid.height,
+                                app.recording_region.width,
+                                app.recording_region.height,
                                 4,
                                 tick_ms,
                             ) {
                                 Ok(filename) => {
-                                    app.last_export_msg =
-                                        Some(format!("Saved {} ({} frames)", filename, frames.len()));
+                                    app.status_msg = Some(format!(
+                                        "Saved {} ({} frames)",
+                                        filename,
+                                        frames.len()
+                                    ));
                                 }
                                 Err(e) => {
-                                    app.last_export_msg = Some(format!("Export failed: {}", e));
+                                    app.status_msg = Some(format!("Export failed: {}", e));
                                 }
                             }
                         } else {
                             app.start_recording();
-                            app.last_export_msg = None;
+                            app.status_msg = None;
                         }
                     }
+                    KeyCode::Char('w') => app.open_prompt(app::PromptKind::SavePattern),
+                    KeyCode::Char('S') => app.open_prompt(app::PromptKind::SaveSession),
+                    KeyCode::Char('L') => app.open_prompt(app::PromptKind::LoadSession),
                     KeyCode::Esc => {
-                        app.pattern_mode = false;
+                        app.stop_placing();
+                        app.selection_anchor = None;
                     }
                     _ => {}
                 }
@@ -118,19 +261,14 @@ fn run(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> io::Result<()>
                 app.recording_capped = false;
                 let frames = app.stop_recording();
                 let tick_ms = app.tick_rate.as_millis() as u16;
-                match export::encode_gif(
-                    &frames,
-                    app.grid.width,
-                    app.grid.height,
-                    4,
-                    tick_ms,
-                ) {
+                let region = app.recording_region;
+                match export::encode_gif(&frames, region.width, region.height, 4, tick_ms) {
                     Ok(filename) => {
-                        app.last_export_msg =
+                        app.status_msg =
                             Some(format!("Auto-saved {} ({} frames)", filename, frames.len()));
                     }
                     Err(e) => {
-                        app.last_export_msg = Some(format!("Export failed: {}", e));
+                        app.status_msg = Some(format!("Export failed: {}", e));
                     }
                 }
             }
@@ -138,3 +276,36 @@ fn run(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> io::Result<()>
         }
     }
 }
+
+/// Left button draws, right button erases, the middle button or shift-drag
+/// pans and the wheel zooms around the pointer. While placing a pattern, a
+/// left click stamps it at the clicked cell. Clicking or dragging on the
+/// minimap moves the view there.
+fn handle_mouse(app: &mut app::App, mouse: MouseEvent) {
+    let (column, row) = (mouse.column, mouse.row);
+    if app.prompt.is_some() || app.library_open {
+        return;
+    }
+    match mouse.kind {
+        MouseEventKind::Down(MouseButton::Middle) => app.start_pan(column, row),
+        MouseEventKind::Down(MouseButton::Left)
+            if mouse.modifiers.contains(KeyModifiers::SHIFT) =>
+        {
+            app.start_pan(column, row)
+        }
+        MouseEventKind::Down(MouseButton::Left) if app.start_minimap_drag(column, row) => {}
+        MouseEventKind::Down(MouseButton::Left) if app.placing.is_some() => {
+            app.stamp_at(column, row)
+        }
+        MouseEventKind::Down(MouseButton::Left) => app.start_paint(column, row, 1),
+        MouseEventKind::Down(MouseButton::Right) => app.start_paint(column, row, 0),
+        MouseEventKind::Drag(_) => app.drag_to(column, row),
+        MouseEventKind::Moved if app.placing.is_some() => {
+            app.point_at(column, row);
+        }
+        MouseEventKind::Up(_) => app.end_drag(),
+        MouseEventKind::ScrollUp => app.zoom_at(column, row, 1),
+        MouseEventKind::ScrollDown => app.zoom_at(column, row, -1),
+        _ => {}
+    }
+}
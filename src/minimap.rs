@@ -0,0 +1,263 @@
+//! A braille overview of the whole universe, drawn in a corner of the grid.
+//!
+//! Each braille character holds 2×4 dots and each dot stands for a square
+//! block of cells, lit when any of them is alive. How full a character's
+//! blocks are sets its brightness. Large populations are sampled rather
+//! than counted, so sparse blocks may go dark.
+
+use crate::app::App;
+use crate::universe::Region;
+
+/// Terminal position of the minimap's top-left border corner, just inside
+/// the grid border.
+pub const ORIGIN: (u16, u16) = (1, 1);
+/// Largest minimap, in characters.
+const MAX_COLS: usize = 32;
+const MAX_ROWS: usize = 12;
+/// Populations up to this are counted cell by cell; above it each dot is
+/// sampled at up to `SAMPLES`² cells.
+const EXACT_CELLS: usize = 1 << 16;
+const SAMPLES: usize = 4;
+
+/// Bit of the braille pattern for the dot at column `dx` (0-1) and row `dy` (0-3).
+pub fn braille_bit(dx: usize, dy: usize) -> u8 {
+    const BITS: [[u8; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+    BITS[dy][dx]
+}
+
+pub fn braille(bits: u8) -> char {
+    char::from_u32(0x2800 + bits as u32).unwrap()
+}
+
+pub struct Minimap {
+    /// Cells covered: the whole grid, or the live cells and view of an
+    /// infinite plane.
+    pub region: Region,
+    /// Cells per dot along each side.
+    pub scale: usize,
+    pub cols: usize,
+    pub rows: usize,
+}
+
+impl Minimap {
+    /// Layout of the minimap for the current state, if there is room for one.
+    pub fn new(app: &App) -> Option<Self> {
+        let region = match app.universe.bounds() {
+            Some(bounds) => bounds,
+            None => {
+                let view = app.view_region();
+                let corners = [
+                    (view.x, view.y),
+                    (
+                        view.x + view.width as isize - 1,
+                        view.y + view.height as isize - 1,
+                    ),
+                ];
+                let live = app.universe.bounding_box().map(|b| {
+                    [
+                        (b.x, b.y),
+                        (b.x + b.width as isize - 1, b.y + b.height as isize - 1),
+                    ]
+                });
+                Region::bounding(corners.into_iter().chain(live.into_iter().flatten()))?
+            }
+        };
+        // Leave at least a few cells of the grid visible beside the map
+        let max_cols = MAX_COLS.min((app.view_width * 2).saturating_sub(8));
+        let max_rows = MAX_ROWS.min(app.view_height.saturating_sub(4));
+        if max_cols < 4 || max_rows < 2 {
+            return None;
+        }
+        let scale = region
+            .width
+            .div_ceil(max_cols * 2)
+            .max(region.height.div_ceil(max_rows * 4))
+            .max(1);
+        Some(Self {
+            region,
+            scale,
+            cols: region.width.div_ceil(scale * 2),
+            rows: region.height.div_ceil(scale * 4),
+        })
+    }
+
+    /// Braille characters row by row, each with the fraction of the cells
+    /// under it that are alive. Small populations are counted exactly; larger
+    /// ones are sampled at a few cells per dot so a frame stays cheap.
+    pub fn render(&self, app: &App) -> Vec<Vec<(char, f64)>> {
+        let (dots_w, dots_h) = (self.cols * 2, self.rows * 4);
+        let mut fill = vec![0.0; dots_w * dots_h];
+        if app.universe.population() <= EXACT_CELLS {
+            let area = (self.scale * self.scale) as f64;
+            for (x, y, _) in app.universe.live_cells() {
+                if self.region.contains(x, y) {
+                    let dx = (x - self.region.x) as usize / self.scale;
+                    let dy = (y - self.region.y) as usize / self.scale;
+                    fill[dy * dots_w + dx] += 1.0 / area;
+                }
+            }
+        } else {
+            let samples = self.scale.min(SAMPLES);
+            let offsets: Vec<isize> = (0..samples)
+                .map(|i| ((2 * i + 1) * self.scale / (2 * samples)) as isize)
+                .collect();
+            let weight = 1.0 / (samples * samples) as f64;
+            for dy in 0..dots_h {
+                for dx in 0..dots_w {
+                    let x = self.region.x + (dx * self.scale) as isize;
+                    let y = self.region.y + (dy * self.scale) as isize;
+                    for &oy in &offsets {
+                        for &ox in &offsets {
+                            if app.universe.get(x + ox, y + oy) > 0 {
+                                fill[dy * dots_w + dx] += weight;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        (0..self.rows)
+            .map(|row| {
+                (0..self.cols)
+                    .map(|col| {
+                        let mut bits = 0;
+                        let mut live = 0.0;
+                        for dy in 0..4 {
+                            for dx in 0..2 {
+                                let f = fill[(row * 4 + dy) * dots_w + col * 2 + dx];
+                                if f > 0.0 {
+                                    bits |= braille_bit(dx, dy);
+                                    live += f;
+                                }
+                            }
+                        }
+                        (braille(bits), live / 8.0)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Universe cell at the middle of the character at `(col, row)`, or at
+    /// the middle of its cells inside the region on the last row and column.
+    pub fn cell(&self, col: usize, row: usize) -> (isize, isize) {
+        let middle = |start: usize, end: usize, extent: usize| {
+            let end = end.min(extent);
+            (start + end.saturating_sub(start) / 2) as isize
+        };
+        (
+            self.region.x
+                + middle(
+                    col * 2 * self.scale,
+                    (col + 1) * 2 * self.scale,
+                    self.region.width,
+                ),
+            self.region.y
+                + middle(
+                    row * 4 * self.scale,
+                    (row + 1) * 4 * self.scale,
+                    self.region.height,
+                ),
+        )
+    }
+
+    /// Character of the minimap at a terminal position, if it is on one.
+    pub fn char_at(&self, column: u16, row: u16) -> Option<(usize, usize)> {
+        let col = (column as usize).checked_sub(ORIGIN.0 as usize + 1)?;
+        let row = (row as usize).checked_sub(ORIGIN.1 as usize + 1)?;
+        (col < self.cols && row < self.rows).then_some((col, row))
+    }
+
+    /// Character of the minimap closest to a terminal position.
+    pub fn nearest_char(&self, column: u16, row: u16) -> (usize, usize) {
+        (
+            (column as usize)
+                .saturating_sub(ORIGIN.0 as usize + 1)
+                .min(self.cols - 1),
+            (row as usize)
+                .saturating_sub(ORIGIN.1 as usize + 1)
+                .min(self.rows - 1),
+        )
+    }
+
+    /// Whether the character at `(col, row)` shows cells inside the view.
+    pub fn in_view(&self, app: &App, col: usize, row: usize) -> bool {
+        let (x, y) = self.cell(col, row);
+        let view = app.view_region();
+        match app.universe.bounds().filter(|_| app.wraps()) {
+            // The view may run past the edges and show cells from the far side
+            Some(b) => {
+                (x - view.x).rem_euclid(b.width as isize) < view.width as isize
+                    && (y - view.y).rem_euclid(b.height as isize) < view.height as isize
+            }
+            None => view.contains(x, y),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn downsamples_the_whole_grid() {
+        let mut app = App::new(256, 256, 60, 30);
+        app.universe.set(0, 0, 1);
+        app.universe.set(255, 255, 1);
+        app.universe.set(9, 0, 1);
+        let map = Minimap::new(&app).unwrap();
+        // 256 cells over at most 64 dots across and 48 down
+        assert_eq!((map.scale, map.cols, map.rows), (6, 22, 11));
+        let chars = map.render(&app);
+        assert_eq!(chars[0][0].0, braille(0x01 | 0x08));
+        assert_eq!(chars[10][21].0, braille(0x04));
+        assert_eq!(chars[5][5].0, braille(0));
+        assert!((chars[0][0].1 - 2.0 / 288.0).abs() < 1e-9);
+
+        assert!(map.in_view(&app, 0, 0));
+        assert!(!map.in_view(&app, 21, 10));
+    }
+
+    #[test]
+    fn samples_large_populations() {
+        let mut app = App::new(1024, 1024, 60, 30);
+        for y in 0..512 {
+            for x in 0..512 {
+                app.universe.set(x, y, 1);
+            }
+        }
+        assert!(app.universe.population() > EXACT_CELLS);
+        let map = Minimap::new(&app).unwrap();
+        assert_eq!((map.scale, map.cols, map.rows), (22, 24, 12));
+        let chars = map.render(&app);
+        assert_eq!(chars[0][0], (braille(0xff), 1.0));
+        assert_eq!(chars[10][20].0, braille(0));
+    }
+
+    #[test]
+    fn covers_the_view_and_live_cells_of_an_infinite_plane() {
+        let mut app = App::with_universe(crate::universe::Engine::Tiled.create((0, 0)), 20, 20);
+        app.universe.set(-500, 40, 1);
+        let map = Minimap::new(&app).unwrap();
+        assert!(map.region.contains(-500, 40));
+        assert!(map.region.contains(19, 19));
+        assert_eq!(map.region.x, -500);
+    }
+
+    #[test]
+    fn dragging_the_view_rectangle_moves_the_view() {
+        let mut app = App::new(256, 256, 60, 30);
+        assert!(!app.start_minimap_drag(10, 5));
+        app.show_minimap = true;
+        // Character (8, 3) covers cells from (96, 72)
+        assert!(app.start_minimap_drag(10, 5));
+        assert_eq!((app.viewport_x, app.viewport_y), (102 - 30, 84 - 15));
+        // Positions past the map snap to its nearest character
+        app.drag_to(200, 100);
+        assert_eq!((app.viewport_x, app.viewport_y), (254 - 30, 248 - 15));
+        app.end_drag();
+        app.nudge_view(-1, 0);
+        assert_eq!(app.viewport_x, 254 - 30 - 12);
+        assert!(!app.start_minimap_drag(40, 5));
+    }
+}
@@ -1,18 +1,279 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::rule::{ParseRuleError, Rule};
+
+pub mod life;
+pub mod plaintext;
+pub mod rle;
+
+/// Most live cells a pattern file may hold; a file claiming more is taken to be corrupt.
+pub const MAX_CELLS: usize = crate::app::MAX_GRID_SIZE * crate::app::MAX_GRID_SIZE;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Rle,
+    Plaintext,
+    Life105,
+    Life106,
+}
+
+impl Format {
+    /// Format implied by a file extension. `.lif` files are written as Life 1.06;
+    /// when reading, the `#Life` header takes precedence.
+    pub fn from_path(path: &Path) -> Option<Format> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "rle" => Some(Format::Rle),
+            "cells" => Some(Format::Plaintext),
+            "lif" | "life" => Some(Format::Life106),
+            _ => None,
+        }
+    }
+
+    /// Guesses the format of pattern text from its first meaningful line.
+    pub fn sniff(text: &str) -> Option<Format> {
+        let first = text.lines().map(str::trim).find(|l| !l.is_empty())?;
+        if first.starts_with(life::HEADER_105) {
+            Some(Format::Life105)
+        } else if first.starts_with(life::HEADER_106) {
+            Some(Format::Life106)
+        } else if first.starts_with('!') || first.chars().all(|c| matches!(c, '.' | 'O')) {
+            Some(Format::Plaintext)
+        } else if first.starts_with('#') || first.starts_with('x') {
+            Some(Format::Rle)
+        } else {
+            None
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Rle => "RLE",
+            Format::Plaintext => "plaintext",
+            Format::Life105 => "Life 1.05",
+            Format::Life106 => "Life 1.06",
+        }
+    }
+}
+
+pub fn parse(text: &str, format: Format) -> Result<Pattern, PatternError> {
+    match format {
+        Format::Rle => rle::parse(text),
+        Format::Plaintext => plaintext::parse(text),
+        Format::Life105 => life::parse_105(text),
+        Format::Life106 => life::parse_106(text),
+    }
+}
+
+pub fn write(pattern: &Pattern, format: Format) -> String {
+    match format {
+        Format::Rle => rle::write(pattern),
+        Format::Plaintext => plaintext::write(pattern),
+        Format::Life105 => life::write_105(pattern),
+        Format::Life106 => life::write_106(pattern),
+    }
+}
+
+/// Live cell offsets relative to the placement point. Built-in patterns borrow
+/// static data; patterns read from files own theirs.
+#[derive(Clone, Debug)]
 pub struct Pattern {
-    pub name: &'static str,
-    pub cells: &'static [(isize, isize)],
+    pub name: Cow<'static, str>,
+    pub cells: Cow<'static, [(isize, isize)]>,
+    pub rule: Option<Rule>,
+    pub comments: Vec<String>,
+}
+
+impl Pattern {
+    /// Builds an owned pattern, shifting `cells` so the bounding box is centered on the origin.
+    pub fn from_cells(name: String, mut cells: Vec<(isize, isize)>) -> Self {
+        if let Some((min_x, min_y, max_x, max_y)) = bounding_box(&cells) {
+            let (cx, cy) = ((min_x + max_x) / 2, (min_y + max_y) / 2);
+            for cell in &mut cells {
+                cell.0 -= cx;
+                cell.1 -= cy;
+            }
+        }
+        cells.sort_by_key(|&(x, y)| (y, x));
+        Self {
+            name: Cow::Owned(name),
+            cells: Cow::Owned(cells),
+            rule: None,
+            comments: Vec::new(),
+        }
+    }
+}
+
+/// One of the eight rotations and reflections of a pattern: an optional
+/// horizontal mirror followed by `turns` quarter turns clockwise.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Transform {
+    pub turns: u8,
+    pub mirrored: bool,
+}
+
+impl Transform {
+    pub fn rotate_clockwise(self) -> Self {
+        Self {
+            turns: (self.turns + 1) % 4,
+            ..self
+        }
+    }
+
+    pub fn rotate_counterclockwise(self) -> Self {
+        Self {
+            turns: (self.turns + 3) % 4,
+            ..self
+        }
+    }
+
+    /// Mirrors left to right on screen, after the current orientation.
+    pub fn flip_horizontal(self) -> Self {
+        Self {
+            turns: (4 - self.turns) % 4,
+            mirrored: !self.mirrored,
+        }
+    }
+
+    /// Mirrors top to bottom on screen, after the current orientation.
+    pub fn flip_vertical(self) -> Self {
+        Self {
+            turns: (6 - self.turns) % 4,
+            mirrored: !self.mirrored,
+        }
+    }
+
+    pub fn apply(self, (mut x, mut y): (isize, isize)) -> (isize, isize) {
+        if self.mirrored {
+            x = -x;
+        }
+        for _ in 0..self.turns {
+            (x, y) = (-y, x);
+        }
+        (x, y)
+    }
+}
+
+impl fmt::Display for Transform {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}°", self.turns as u32 * 90)?;
+        if self.mirrored {
+            write!(f, " mirrored")?;
+        }
+        Ok(())
+    }
+}
+
+impl Pattern {
+    /// Owned copy of the pattern with `transform` applied to every cell.
+    pub fn transformed(&self, transform: Transform) -> Pattern {
+        let mut cells: Vec<_> = self.cells.iter().map(|&c| transform.apply(c)).collect();
+        cells.sort_by_key(|&(x, y)| (y, x));
+        Pattern {
+            name: self.name.clone(),
+            cells: Cow::Owned(cells),
+            rule: self.rule,
+            comments: self.comments.clone(),
+        }
+    }
+}
+
+/// `(min_x, min_y, max_x, max_y)` of the given cells, or `None` if there are none.
+pub fn bounding_box(cells: &[(isize, isize)]) -> Option<(isize, isize, isize, isize)> {
+    let first = *cells.first()?;
+    Some(cells.iter().fold(
+        (first.0, first.1, first.0, first.1),
+        |(x0, y0, x1, y1), &(x, y)| (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+    ))
+}
+
+#[derive(Debug)]
+pub enum PatternError {
+    Io(io::Error),
+    MissingHeader,
+    InvalidHeader(String),
+    InvalidRule(ParseRuleError),
+    UnexpectedChar {
+        line: usize,
+        ch: char,
+    },
+    InvalidCoordinates {
+        line: usize,
+    },
+    Truncated,
+    /// More live cells than `MAX_CELLS`.
+    TooLarge,
+    UnknownFormat,
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PatternError::Io(e) => write!(f, "{}", e),
+            PatternError::MissingHeader => write!(f, "missing 'x = .., y = ..' header"),
+            PatternError::InvalidHeader(h) => write!(f, "invalid header '{}'", h),
+            PatternError::InvalidRule(e) => write!(f, "{}", e),
+            PatternError::UnexpectedChar { line, ch } => {
+                write!(f, "unexpected '{}' on line {}", ch, line)
+            }
+            PatternError::InvalidCoordinates { line } => {
+                write!(f, "invalid coordinates on line {}", line)
+            }
+            PatternError::Truncated => write!(f, "pattern data ends mid-run"),
+            PatternError::TooLarge => write!(f, "pattern has more than {} cells", MAX_CELLS),
+            PatternError::UnknownFormat => write!(f, "unrecognized pattern format"),
+        }
+    }
+}
+
+impl std::error::Error for PatternError {}
+
+impl From<io::Error> for PatternError {
+    fn from(e: io::Error) -> Self {
+        PatternError::Io(e)
+    }
+}
+
+impl From<ParseRuleError> for PatternError {
+    fn from(e: ParseRuleError) -> Self {
+        PatternError::InvalidRule(e)
+    }
+}
+
+/// Reads a pattern file in any supported format, using the file stem as the
+/// name unless the file provides one.
+pub fn load(path: &Path) -> Result<Pattern, PatternError> {
+    let text = fs::read_to_string(path)?;
+    let format = match (Format::sniff(&text), Format::from_path(path)) {
+        (Some(sniffed @ (Format::Life105 | Format::Life106)), _) => sniffed,
+        (_, Some(by_ext)) => by_ext,
+        (Some(sniffed), None) => sniffed,
+        (None, None) => return Err(PatternError::UnknownFormat),
+    };
+    let mut pattern = parse(&text, format)?;
+    if pattern.name.is_empty() {
+        let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned());
+        pattern.name = Cow::Owned(stem.unwrap_or_default());
+    }
+    Ok(pattern)
 }
 
 #[rustfmt::skip]
 pub const GLIDER: Pattern = Pattern {
-    name: "glider",
-    cells: &[(0, -1), (1, 0), (-1, 1), (0, 1), (1, 1)],
+    name: Cow::Borrowed("glider"),
+    cells: Cow::Borrowed(&[(0, -1), (1, 0), (-1, 1), (0, 1), (1, 1)]),
+    rule: None,
+    comments: Vec::new(),
 };
 
 #[rustfmt::skip]
 pub const PULSAR: Pattern = Pattern {
-    name: "pulsar",
-    cells: &[
+    name: Cow::Borrowed("pulsar"),
+    cells: Cow::Borrowed(&[
         // Top-left quadrant (and mirrored)
         (-2, -1), (-3, -1), (-4, -1),
         (-1, -2), (-1, -3), (-1, -4),
@@ -30,13 +291,15 @@ pub const PULSAR: Pattern = Pattern {
         (-6, 2), (-6, 3), (-6, 4),
         (2, 6), (3, 6), (4, 6),
         (6, 2), (6, 3), (6, 4),
-    ],
+    ]),
+    rule: None,
+    comments: Vec::new(),
 };
 
 #[rustfmt::skip]
 pub const GLIDER_GUN: Pattern = Pattern {
-    name: "glider gun",
-    cells: &[
+    name: Cow::Borrowed("glider gun"),
+    cells: Cow::Borrowed(&[
         (0, 4), (0, 5),
         (1, 4), (1, 5),
         (10, 4), (10, 5), (10, 6),
@@ -53,7 +316,94 @@ pub const GLIDER_GUN: Pattern = Pattern {
         (24, 0), (24, 1), (24, 5), (24, 6),
         (34, 2), (34, 3),
         (35, 2), (35, 3),
-    ],
+    ]),
+    rule: None,
+    comments: Vec::new(),
 };
 
 pub const ALL: &[&Pattern] = &[&GLIDER, &PULSAR, &GLIDER_GUN];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sniffs_formats() {
+        assert_eq!(
+            Format::sniff("#N Glider\nx = 3, y = 3\n"),
+            Some(Format::Rle)
+        );
+        assert_eq!(Format::sniff("x = 3, y = 3\n3o!"), Some(Format::Rle));
+        assert_eq!(
+            Format::sniff("!Name: Glider\n.O\n"),
+            Some(Format::Plaintext)
+        );
+        assert_eq!(Format::sniff("\n.O..\nOOO\n"), Some(Format::Plaintext));
+        assert_eq!(Format::sniff("#Life 1.05\n*\n"), Some(Format::Life105));
+        assert_eq!(Format::sniff("#Life 1.06\n0 0\n"), Some(Format::Life106));
+        assert_eq!(Format::sniff("0 0\n"), None);
+    }
+
+    #[test]
+    fn formats_agree_on_builtins() {
+        let formats = [
+            Format::Rle,
+            Format::Plaintext,
+            Format::Life105,
+            Format::Life106,
+        ];
+        for builtin in ALL {
+            let reference = Pattern::from_cells(String::new(), builtin.cells.to_vec());
+            for format in formats {
+                let parsed = parse(&write(builtin, format), format).unwrap();
+                assert_eq!(
+                    parsed.cells,
+                    reference.cells,
+                    "{} via {}",
+                    builtin.name,
+                    format.name()
+                );
+            }
+        }
+    }
+
+    /// Cells shifted so the bounding box starts at the origin, in order.
+    fn normalized(cells: impl Iterator<Item = (isize, isize)>) -> Vec<(isize, isize)> {
+        let mut cells: Vec<_> = cells.collect();
+        let (x0, y0, _, _) = bounding_box(&cells).unwrap();
+        for cell in &mut cells {
+            *cell = (cell.0 - x0, cell.1 - y0);
+        }
+        cells.sort_by_key(|&(x, y)| (y, x));
+        cells
+    }
+
+    #[test]
+    fn transforms_form_the_dihedral_group() {
+        let mut orientations = Vec::new();
+        for turns in 0..4 {
+            for mirrored in [false, true] {
+                let t = Transform { turns, mirrored };
+                assert_eq!(t.flip_horizontal().flip_horizontal(), t);
+                assert_eq!(t.flip_vertical().flip_vertical(), t);
+                assert_eq!(t.rotate_clockwise().rotate_counterclockwise(), t);
+                let cells = GLIDER.transformed(t).cells.into_owned();
+                let flipped = GLIDER.transformed(t.flip_horizontal()).cells;
+                assert_eq!(
+                    normalized(flipped.iter().copied()),
+                    normalized(cells.iter().map(|&(x, y)| (-x, y)))
+                );
+                let flipped = GLIDER.transformed(t.flip_vertical()).cells;
+                assert_eq!(
+                    normalized(flipped.iter().copied()),
+                    normalized(cells.iter().map(|&(x, y)| (x, -y)))
+                );
+                orientations.push(normalized(cells.into_iter()));
+            }
+        }
+        // A glider looks different in each of the eight orientations
+        orientations.sort();
+        orientations.dedup();
+        assert_eq!(orientations.len(), 8);
+    }
+}
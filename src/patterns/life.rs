@@ -0,0 +1,158 @@
+//! Life 1.05 (blocks of `.`/`*` rows) and Life 1.06 (one `x y` pair per line).
+
+use super::{Pattern, PatternError, bounding_box};
+use crate::rule::Rule;
+
+pub const HEADER_105: &str = "#Life 1.05";
+pub const HEADER_106: &str = "#Life 1.06";
+
+fn parse_pair(s: &str, line: usize) -> Result<(isize, isize), PatternError> {
+    let mut parts = s.split_whitespace().map(str::parse::<isize>);
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(Ok(x)), Some(Ok(y)), None) => Ok((x, y)),
+        _ => Err(PatternError::InvalidCoordinates { line }),
+    }
+}
+
+pub fn parse_105(text: &str) -> Result<Pattern, PatternError> {
+    let mut comments = Vec::new();
+    let mut rule = None;
+    let mut cells = Vec::new();
+    let (mut block_x, mut y) = (0isize, 0isize);
+
+    for (line_no, line) in text.lines().enumerate() {
+        let line = line.trim_end();
+        if let Some(directive) = line.strip_prefix('#') {
+            let mut chars = directive.chars();
+            let tag = chars.next();
+            let arg = chars.as_str().trim();
+            match tag {
+                Some('D') => comments.push(arg.to_string()),
+                Some('N') => rule = Some(Rule::LIFE),
+                Some('R') => rule = Some(arg.parse()?),
+                Some('P') => (block_x, y) = parse_pair(arg, line_no + 1)?,
+                _ => {}
+            }
+            continue;
+        }
+        for (dx, ch) in line.chars().enumerate() {
+            match ch {
+                '.' => {}
+                '*' => cells.push((block_x + dx as isize, y)),
+                ch => {
+                    return Err(PatternError::UnexpectedChar {
+                        line: line_no + 1,
+                        ch,
+                    });
+                }
+            }
+        }
+        y += 1;
+    }
+
+    let mut pattern = Pattern::from_cells(String::new(), cells);
+    pattern.rule = rule;
+    pattern.comments = comments;
+    Ok(pattern)
+}
+
+pub fn parse_106(text: &str) -> Result<Pattern, PatternError> {
+    let mut cells = Vec::new();
+    for (line_no, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        cells.push(parse_pair(line, line_no + 1)?);
+    }
+    Ok(Pattern::from_cells(String::new(), cells))
+}
+
+/// Encodes `pattern` as a single Life 1.05 block. Non-Life rules are written in
+/// the format's survival/birth `#R` notation.
+pub fn write_105(pattern: &Pattern) -> String {
+    let mut out = format!("{}\n", HEADER_105);
+    if !pattern.name.is_empty() {
+        out.push_str(&format!("#D {}\n", pattern.name));
+    }
+    for comment in &pattern.comments {
+        out.push_str(&format!("#D {}\n", comment));
+    }
+    match pattern.rule.unwrap_or_default() {
+        Rule::LIFE => out.push_str("#N\n"),
+        rule => {
+            let bs = rule.to_string();
+            let (birth, survival) = bs.split_once('/').unwrap_or_default();
+            out.push_str(&format!("#R {}/{}\n", &survival[1..], &birth[1..]));
+        }
+    }
+
+    let Some((min_x, min_y, max_x, max_y)) = bounding_box(&pattern.cells) else {
+        return out;
+    };
+    out.push_str(&format!("#P {} {}\n", min_x, min_y));
+    let width = (max_x - min_x + 1) as usize;
+    let mut rows = vec![vec!['.'; width]; (max_y - min_y + 1) as usize];
+    for &(x, y) in pattern.cells.iter() {
+        rows[(y - min_y) as usize][(x - min_x) as usize] = '*';
+    }
+    for row in rows {
+        let line: String = row.into_iter().collect();
+        out.push_str(line.trim_end_matches('.'));
+        out.push('\n');
+    }
+    out
+}
+
+pub fn write_106(pattern: &Pattern) -> String {
+    let mut out = format!("{}\n", HEADER_106);
+    for &(x, y) in pattern.cells.iter() {
+        out.push_str(&format!("{} {}\n", x, y));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_105_blocks() {
+        let text = "#Life 1.05\n#D Two blinkers\n#R 23/36\n#P -1 0\n***\n#P 5 5\n*\n*\n*\n";
+        let pattern = parse_105(text).unwrap();
+        assert_eq!(pattern.comments, vec!["Two blinkers"]);
+        assert_eq!(pattern.rule.unwrap().to_string(), "B36/S23");
+        assert_eq!(pattern.cells.len(), 6);
+        assert_eq!(bounding_box(&pattern.cells), Some((-3, -3, 3, 4)));
+    }
+
+    #[test]
+    fn parses_106_coordinates() {
+        let pattern = parse_106("#Life 1.06\n0 -1\n1 0\n-1 1\n0 1\n1 1\n").unwrap();
+        assert_eq!(
+            pattern.cells.as_ref(),
+            &[(0, -1), (1, 0), (-1, 1), (0, 1), (1, 1)]
+        );
+        assert!(matches!(
+            parse_106("#Life 1.06\n0 0\n1 x\n"),
+            Err(PatternError::InvalidCoordinates { line: 3 })
+        ));
+    }
+
+    #[test]
+    fn writers_round_trip() {
+        let mut pattern = parse_106("#Life 1.06\n0 0\n1 0\n2 0\n5 3\n").unwrap();
+        pattern.rule = Some("B36/S23".parse().unwrap());
+
+        let text = write_105(&pattern);
+        assert!(text.contains("#R 23/36\n"));
+        let from_105 = parse_105(&text).unwrap();
+        assert_eq!(from_105.cells, pattern.cells);
+        assert_eq!(from_105.rule, pattern.rule);
+
+        assert_eq!(
+            parse_106(&write_106(&pattern)).unwrap().cells,
+            pattern.cells
+        );
+    }
+}
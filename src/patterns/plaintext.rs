@@ -0,0 +1,95 @@
+use super::{Pattern, PatternError, bounding_box};
+
+/// Parses the LifeWiki plaintext (`.cells`) format: `!` comment lines, then rows
+/// of `.` for dead and `O` for live cells.
+pub fn parse(text: &str) -> Result<Pattern, PatternError> {
+    let mut name = String::new();
+    let mut comments = Vec::new();
+    let mut cells = Vec::new();
+    let mut y = 0isize;
+
+    for (line_no, line) in text.lines().enumerate() {
+        if let Some(comment) = line.strip_prefix('!') {
+            match comment.strip_prefix("Name:") {
+                Some(n) => name = n.trim().to_string(),
+                None => comments.push(comment.trim().to_string()),
+            }
+            continue;
+        }
+        for (x, ch) in line.trim_end().chars().enumerate() {
+            match ch {
+                '.' => {}
+                'O' | 'o' | '*' => cells.push((x as isize, y)),
+                ch => {
+                    return Err(PatternError::UnexpectedChar {
+                        line: line_no + 1,
+                        ch,
+                    });
+                }
+            }
+        }
+        y += 1;
+    }
+
+    let mut pattern = Pattern::from_cells(name, cells);
+    pattern.comments = comments;
+    Ok(pattern)
+}
+
+/// Encodes `pattern` as plaintext, dropping trailing dead cells on each row.
+pub fn write(pattern: &Pattern) -> String {
+    let mut out = String::new();
+    if !pattern.name.is_empty() {
+        out.push_str(&format!("!Name: {}\n", pattern.name));
+    }
+    for comment in &pattern.comments {
+        out.push_str(&format!("!{}\n", comment));
+    }
+
+    let Some((min_x, min_y, max_x, max_y)) = bounding_box(&pattern.cells) else {
+        return out;
+    };
+    let width = (max_x - min_x + 1) as usize;
+    let mut rows = vec![vec!['.'; width]; (max_y - min_y + 1) as usize];
+    for &(x, y) in pattern.cells.iter() {
+        rows[(y - min_y) as usize][(x - min_x) as usize] = 'O';
+    }
+    for row in rows {
+        let line: String = row.into_iter().collect();
+        out.push_str(line.trim_end_matches('.'));
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GLIDER: &str = "!Name: Glider\n!A small spaceship\n.O\n..O\nOOO\n";
+
+    #[test]
+    fn parses_glider() {
+        let pattern = parse(GLIDER).unwrap();
+        assert_eq!(pattern.name, "Glider");
+        assert_eq!(pattern.comments, vec!["A small spaceship"]);
+        assert_eq!(
+            pattern.cells.as_ref(),
+            &[(0, -1), (1, 0), (-1, 1), (0, 1), (1, 1)]
+        );
+    }
+
+    #[test]
+    fn write_round_trips() {
+        let pattern = parse(GLIDER).unwrap();
+        assert_eq!(write(&pattern), GLIDER);
+    }
+
+    #[test]
+    fn rejects_unknown_characters() {
+        assert!(matches!(
+            parse("!x\n.O\n.X\n"),
+            Err(PatternError::UnexpectedChar { line: 3, ch: 'X' })
+        ));
+    }
+}
@@ -0,0 +1,295 @@
+use super::{MAX_CELLS, Pattern, PatternError, bounding_box};
+use crate::rule::Rule;
+
+/// Maximum line length of the encoded body, as recommended by the format.
+const LINE_WIDTH: usize = 70;
+
+/// Width, height and rule from a `x = .., y = .., rule = ..` header.
+fn parse_header(line: &str) -> Result<(usize, usize, Option<Rule>), PatternError> {
+    let invalid = || PatternError::InvalidHeader(line.to_string());
+    let (mut width, mut height, mut rule) = (None, None, None);
+
+    let mut rest = line;
+    while !rest.is_empty() {
+        let (field, tail) = rest.split_once(',').unwrap_or((rest, ""));
+        let (key, value) = field.split_once('=').ok_or_else(invalid)?;
+        let value = value.trim();
+        match key.trim() {
+            "x" => width = value.parse::<usize>().ok(),
+            "y" => height = value.parse::<usize>().ok(),
+            // The rule runs to the end of the line, since Golly appends
+            // bounded-grid suffixes such as `:T100,100`
+            "rule" => {
+                let value = rest.split_once('=').ok_or_else(invalid)?.1;
+                rule = Some(value.split(':').next().unwrap_or(value).trim().parse()?);
+                break;
+            }
+            _ => {}
+        }
+        rest = tail;
+    }
+
+    match (width, height) {
+        (Some(width), Some(height)) => Ok((width, height, rule)),
+        _ => Err(invalid()),
+    }
+}
+
+/// Parses a Run Length Encoded pattern as published on LifeWiki and used by Golly.
+/// Runs reaching past the size given in the header are rejected, so a corrupt
+/// count cannot demand an unbounded number of cells.
+pub fn parse(text: &str) -> Result<Pattern, PatternError> {
+    let mut name = String::new();
+    let mut comments = Vec::new();
+    let mut lines = text.lines().enumerate();
+
+    let (width, height, rule) = loop {
+        let (_, line) = lines.next().ok_or(PatternError::MissingHeader)?;
+        let line = line.trim();
+        if let Some(rest) = line.strip_prefix('#') {
+            let mut chars = rest.chars();
+            match chars.next() {
+                Some('N') => name = chars.as_str().trim().to_string(),
+                Some('C' | 'c' | 'O') => comments.push(chars.as_str().trim().to_string()),
+                _ => {}
+            }
+        } else if !line.is_empty() {
+            break parse_header(line)?;
+        }
+    };
+
+    let mut cells = Vec::new();
+    let (mut x, mut y) = (0usize, 0usize);
+    let mut count: Option<usize> = None;
+
+    'body: for (line_no, line) in lines {
+        for ch in line.chars() {
+            if ch.is_whitespace() {
+                continue;
+            }
+            if let Some(d) = ch.to_digit(10) {
+                count = Some(
+                    count
+                        .unwrap_or(0)
+                        .saturating_mul(10)
+                        .saturating_add(d as usize),
+                );
+                continue;
+            }
+            let n = count.take().unwrap_or(1);
+            let outside = PatternError::InvalidCoordinates { line: line_no + 1 };
+            match ch {
+                'b' | '.' | 'o' => {
+                    if n > width - x || (ch == 'o' && y >= height) {
+                        return Err(outside);
+                    }
+                    if ch == 'o' {
+                        if n > MAX_CELLS - cells.len() {
+                            return Err(PatternError::TooLarge);
+                        }
+                        cells.extend((x..x + n).map(|cx| (cx as isize, y as isize)));
+                    }
+                    x += n;
+                }
+                '$' => {
+                    if n > height - y {
+                        return Err(outside);
+                    }
+                    y += n;
+                    x = 0;
+                }
+                '!' => break 'body,
+                ch => {
+                    return Err(PatternError::UnexpectedChar {
+                        line: line_no + 1,
+                        ch,
+                    });
+                }
+            }
+        }
+    }
+    if count.is_some() {
+        return Err(PatternError::Truncated);
+    }
+
+    let mut pattern = Pattern::from_cells(name, cells);
+    pattern.rule = rule;
+    pattern.comments = comments;
+    Ok(pattern)
+}
+
+/// Encodes `pattern` as RLE, trimmed to its bounding box. A missing rule is written as Life.
+pub fn write(pattern: &Pattern) -> String {
+    let mut cells = pattern.cells.to_vec();
+    cells.sort_by_key(|&(x, y)| (y, x));
+    cells.dedup();
+    let (min_x, min_y, max_x, max_y) = bounding_box(&cells).unwrap_or((0, 0, -1, -1));
+
+    let mut out = String::new();
+    if !pattern.name.is_empty() {
+        out.push_str(&format!("#N {}\n", pattern.name));
+    }
+    for comment in &pattern.comments {
+        out.push_str(&format!("#C {}\n", comment));
+    }
+    out.push_str(&format!(
+        "x = {}, y = {}, rule = {}\n",
+        max_x - min_x + 1,
+        max_y - min_y + 1,
+        pattern.rule.unwrap_or_default()
+    ));
+
+    let mut tokens = Vec::new();
+    let mut run: Option<(char, isize)> = None;
+    let mut push = |tag: char, n: isize, tokens: &mut Vec<String>| match &mut run {
+        Some((t, count)) if *t == tag => *count += n,
+        _ => {
+            if let Some((t, count)) = run.replace((tag, n)) {
+                tokens.push(token(t, count));
+            }
+        }
+    };
+
+    let (mut x, mut y) = (min_x, min_y);
+    for &(cx, cy) in &cells {
+        if cy > y {
+            push('$', cy - y, &mut tokens);
+            (x, y) = (min_x, cy);
+        }
+        if cx > x {
+            push('b', cx - x, &mut tokens);
+        }
+        push('o', 1, &mut tokens);
+        x = cx + 1;
+    }
+    if let Some((t, count)) = run {
+        tokens.push(token(t, count));
+    }
+    tokens.push("!".to_string());
+
+    let mut line = String::new();
+    for tok in tokens {
+        if line.len() + tok.len() > LINE_WIDTH {
+            out.push_str(&line);
+            out.push('\n');
+            line.clear();
+        }
+        line.push_str(&tok);
+    }
+    out.push_str(&line);
+    out.push('\n');
+    out
+}
+
+fn token(tag: char, count: isize) -> String {
+    if count == 1 {
+        tag.to_string()
+    } else {
+        format!("{}{}", count, tag)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GLIDER: &str = "\
+#N Glider
+#C The smallest spaceship.
+x = 3, y = 3, rule = B3/S23
+bob$2bo$3o!
+";
+
+    #[test]
+    fn parses_glider() {
+        let pattern = parse(GLIDER).unwrap();
+        assert_eq!(pattern.name, "Glider");
+        assert_eq!(pattern.comments, vec!["The smallest spaceship."]);
+        assert_eq!(pattern.rule, Some(Rule::LIFE));
+        assert_eq!(
+            pattern.cells.as_ref(),
+            &[(0, -1), (1, 0), (-1, 1), (0, 1), (1, 1)]
+        );
+    }
+
+    #[test]
+    fn reads_golly_bounded_grid_rules() {
+        let pattern = parse("x = 3, y = 3, rule = B36/S23:T100,100\nbo$2bo$3o!").unwrap();
+        assert_eq!(pattern.rule, Some("B36/S23".parse().unwrap()));
+        assert_eq!(pattern.cells.len(), 5);
+    }
+
+    #[test]
+    fn runs_span_lines_and_blank_rows() {
+        let pattern = parse("x = 4, y = 4\n2o\n2b$2$\n4o!").unwrap();
+        assert_eq!(pattern.cells.len(), 6);
+        assert_eq!(bounding_box(&pattern.cells), Some((-1, -1, 2, 2)));
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        assert!(matches!(
+            parse("#C only comments\n"),
+            Err(PatternError::MissingHeader)
+        ));
+        assert!(matches!(
+            parse("x = 3\nooo!"),
+            Err(PatternError::InvalidHeader(_))
+        ));
+        assert!(matches!(
+            parse("x = 1, y = 1, rule = B9/S23\no!"),
+            Err(PatternError::InvalidRule(_))
+        ));
+        assert!(matches!(
+            parse("x = 2, y = 1\noz!"),
+            Err(PatternError::UnexpectedChar { line: 2, ch: 'z' })
+        ));
+        assert!(matches!(
+            parse("x = 2, y = 1\no12"),
+            Err(PatternError::Truncated)
+        ));
+        // Runs past the declared size, however large
+        assert!(matches!(
+            parse("x = 2, y = 2\n3o!"),
+            Err(PatternError::InvalidCoordinates { line: 2 })
+        ));
+        assert!(matches!(
+            parse("x = 2, y = 2\no2$o!"),
+            Err(PatternError::InvalidCoordinates { line: 2 })
+        ));
+        assert!(matches!(
+            parse("x = 3, y = 1\n99999999999999999999999o!"),
+            Err(PatternError::InvalidCoordinates { line: 2 })
+        ));
+        assert!(matches!(
+            parse("x = 999999999999, y = 1\n999999999999o!"),
+            Err(PatternError::TooLarge)
+        ));
+    }
+
+    #[test]
+    fn write_round_trips() {
+        let glider = parse(GLIDER).unwrap();
+        let text = write(&glider);
+        assert_eq!(
+            text,
+            "#N Glider\n#C The smallest spaceship.\nx = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n"
+        );
+        assert_eq!(parse(&text).unwrap().cells, glider.cells);
+    }
+
+    #[test]
+    fn write_wraps_long_lines() {
+        // Alternating cells over a wide row produce many short tokens
+        let cells: Vec<_> = (0..200)
+            .step_by(2)
+            .map(|x| (x, 0))
+            .chain([(0, 5)])
+            .collect();
+        let pattern = Pattern::from_cells(String::new(), cells);
+        let text = write(&pattern);
+        assert!(text.lines().all(|l| l.len() <= LINE_WIDTH));
+        assert!(text.contains("5$o!"));
+        assert_eq!(parse(&text).unwrap().cells, pattern.cells);
+    }
+}
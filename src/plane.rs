@@ -0,0 +1,272 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::rule::Rule;
+use crate::topology::Topology;
+use crate::universe::{Engine, Region, Universe};
+
+/// Side length of a tile in cells.
+const TILE: usize = 32;
+const PADDED: usize = TILE + 2;
+
+type TileKey = (isize, isize);
+type Tile = Box<[u16; TILE * TILE]>;
+
+/// Unbounded universe stored as square tiles of cell ages, allocated only
+/// where something is alive.
+pub struct Plane {
+    tiles: HashMap<TileKey, Tile>,
+    rule: Rule,
+}
+
+fn locate(x: isize, y: isize) -> (TileKey, usize) {
+    let t = TILE as isize;
+    let key = (x.div_euclid(t), y.div_euclid(t));
+    let idx = y.rem_euclid(t) as usize * TILE + x.rem_euclid(t) as usize;
+    (key, idx)
+}
+
+/// Which padded rows/columns a neighboring tile at offset `d` covers:
+/// `(padded_start, tile_start, len)`.
+fn span(d: isize) -> (usize, usize, usize) {
+    match d {
+        -1 => (0, TILE - 1, 1),
+        0 => (1, 0, TILE),
+        _ => (TILE + 1, 0, 1),
+    }
+}
+
+impl Plane {
+    pub fn new() -> Self {
+        Self {
+            tiles: HashMap::new(),
+            rule: Rule::default(),
+        }
+    }
+
+    /// Copies the live/dead state of tile `key` plus a one-cell border from
+    /// its neighbors into `padded`.
+    fn fill_padded(&self, (tx, ty): TileKey, padded: &mut [u8; PADDED * PADDED]) {
+        padded.fill(0);
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let Some(src) = self.tiles.get(&(tx + dx, ty + dy)) else {
+                    continue;
+                };
+                let (px, sx, w) = span(dx);
+                let (py, sy, h) = span(dy);
+                for row in 0..h {
+                    for col in 0..w {
+                        padded[(py + row) * PADDED + px + col] =
+                            (src[(sy + row) * TILE + sx + col] > 0) as u8;
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Default for Plane {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Universe for Plane {
+    fn get(&self, x: isize, y: isize) -> u16 {
+        let (key, idx) = locate(x, y);
+        self.tiles.get(&key).map_or(0, |tile| tile[idx])
+    }
+
+    fn set(&mut self, x: isize, y: isize, age: u16) {
+        let (key, idx) = locate(x, y);
+        if age > 0 {
+            self.tiles
+                .entry(key)
+                .or_insert_with(|| Box::new([0; TILE * TILE]))[idx] = age;
+        } else if let Some(tile) = self.tiles.get_mut(&key) {
+            tile[idx] = 0;
+        }
+    }
+
+    fn wrap(&self, x: isize, y: isize) -> Option<(isize, isize)> {
+        Some((x, y))
+    }
+
+    fn step(&mut self) {
+        // Only tiles with live cells, and their immediate neighbors, can change
+        let mut targets = HashSet::new();
+        for (&(tx, ty), tile) in &self.tiles {
+            if tile.iter().any(|&age| age > 0) {
+                for dy in -1..=1 {
+                    for dx in -1..=1 {
+                        targets.insert((tx + dx, ty + dy));
+                    }
+                }
+            }
+        }
+
+        let mut next = HashMap::with_capacity(targets.len());
+        let mut padded = [0u8; PADDED * PADDED];
+        for key in targets {
+            self.fill_padded(key, &mut padded);
+            let current = self.tiles.get(&key);
+            let mut tile: Tile = Box::new([0; TILE * TILE]);
+            let mut alive = false;
+
+            for y in 0..TILE {
+                for x in 0..TILE {
+                    let p = (y + 1) * PADDED + x + 1;
+                    let neighbors = padded[p - PADDED - 1]
+                        + padded[p - PADDED]
+                        + padded[p - PADDED + 1]
+                        + padded[p - 1]
+                        + padded[p + 1]
+                        + padded[p + PADDED - 1]
+                        + padded[p + PADDED]
+                        + padded[p + PADDED + 1];
+                    let age = current.map_or(0, |t| t[y * TILE + x]);
+
+                    let next_age = if age > 0 && self.rule.survives(neighbors) {
+                        age.saturating_add(1)
+                    } else if age == 0 && self.rule.born(neighbors) {
+                        1
+                    } else {
+                        0
+                    };
+                    tile[y * TILE + x] = next_age;
+                    alive |= next_age > 0;
+                }
+            }
+            if alive {
+                next.insert(key, tile);
+            }
+        }
+        self.tiles = next;
+    }
+
+    fn population(&self) -> usize {
+        self.tiles
+            .values()
+            .map(|tile| tile.iter().filter(|&&age| age > 0).count())
+            .sum()
+    }
+
+    fn clear(&mut self) {
+        self.tiles.clear();
+    }
+
+    fn live_cells(&self) -> Vec<(isize, isize, u16)> {
+        let t = TILE as isize;
+        let mut cells = Vec::new();
+        for (&(tx, ty), tile) in &self.tiles {
+            for (idx, &age) in tile.iter().enumerate() {
+                if age > 0 {
+                    let (lx, ly) = ((idx % TILE) as isize, (idx / TILE) as isize);
+                    cells.push((tx * t + lx, ty * t + ly, age));
+                }
+            }
+        }
+        cells
+    }
+
+    fn bounds(&self) -> Option<Region> {
+        None
+    }
+
+    fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    fn set_rule(&mut self, rule: Rule) -> Result<(), String> {
+        if rule.born(0) {
+            return Err(format!(
+                "{} fills empty space and cannot run unbounded",
+                rule
+            ));
+        }
+        self.rule = rule;
+        Ok(())
+    }
+
+    fn topology(&self) -> Option<Topology> {
+        None
+    }
+
+    fn set_topology(&mut self, _topology: Topology) {}
+
+    fn engine(&self) -> Engine {
+        Engine::Tiled
+    }
+
+    fn active_at(&self, x: isize, y: isize) -> bool {
+        self.tiles.contains_key(&locate(x, y).0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Grid;
+
+    fn sorted_cells(universe: &dyn Universe, dx: isize, dy: isize) -> Vec<(isize, isize)> {
+        let mut cells: Vec<_> = universe
+            .live_cells()
+            .into_iter()
+            .map(|(x, y, _)| (x + dx, y + dy))
+            .collect();
+        cells.sort();
+        cells
+    }
+
+    #[test]
+    fn glider_crosses_tile_boundaries() {
+        let mut plane = Plane::new();
+        let glider = [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)];
+        for &(x, y) in &glider {
+            plane.set(x - 40, y - 40, 1);
+        }
+        for _ in 0..4 * 60 {
+            plane.step();
+        }
+        // Sixty periods move the glider 60 cells diagonally, from negative to positive tiles
+        let mut expected: Vec<_> = glider.iter().map(|&(x, y)| (x + 20, y + 20)).collect();
+        expected.sort();
+        assert_eq!(sorted_cells(&plane, 0, 0), expected);
+        assert_eq!(plane.population(), 5);
+    }
+
+    #[test]
+    fn matches_grid_away_from_edges() {
+        let mut grid = Grid::new(160, 160);
+        let mut plane = Plane::new();
+        let soup = Region {
+            x: 64,
+            y: 64,
+            width: 32,
+            height: 32,
+        };
+        grid.randomize(soup, 0.4, &mut fastrand::Rng::with_seed(7));
+        for (x, y, age) in grid.live_cells() {
+            plane.set(x - 80, y - 80, age);
+        }
+
+        for _ in 0..40 {
+            grid.step();
+            plane.step();
+            assert_eq!(sorted_cells(&grid, -80, -80), sorted_cells(&plane, 0, 0));
+        }
+        assert!(
+            plane
+                .live_cells()
+                .iter()
+                .all(|&(x, y, age)| grid.get(x + 80, y + 80) == age)
+        );
+    }
+
+    #[test]
+    fn rejects_b0_rules() {
+        let mut plane = Plane::new();
+        assert!(plane.set_rule("B0/S".parse().unwrap()).is_err());
+        assert!(plane.set_rule("B36/S23".parse().unwrap()).is_ok());
+    }
+}
@@ -0,0 +1,179 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// Outer-totalistic Life-like rule. Bit `n` of `birth` / `survival` is set when
+/// a cell with `n` live neighbors is born / stays alive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rule {
+    birth: u16,
+    survival: u16,
+}
+
+/// Well-known rules, selectable by name wherever a rulestring is accepted.
+pub const PRESETS: &[(&str, &str)] = &[
+    ("Life", "B3/S23"),
+    ("HighLife", "B36/S23"),
+    ("Day & Night", "B3678/S34678"),
+    ("Seeds", "B2/S"),
+    ("Maze", "B3/S12345"),
+    ("Life without Death", "B3/S012345678"),
+    ("Replicator", "B1357/S1357"),
+    ("2x2", "B36/S125"),
+    ("Diamoeba", "B35678/S5678"),
+    ("Morley", "B368/S245"),
+];
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseRuleError {
+    Empty,
+    InvalidDigit(char),
+    Malformed(String),
+}
+
+impl fmt::Display for ParseRuleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseRuleError::Empty => write!(f, "empty rulestring"),
+            ParseRuleError::InvalidDigit(c) => write!(f, "invalid neighbor count '{}'", c),
+            ParseRuleError::Malformed(s) => write!(f, "malformed rulestring '{}'", s),
+        }
+    }
+}
+
+impl std::error::Error for ParseRuleError {}
+
+impl Rule {
+    pub const LIFE: Rule = Rule {
+        birth: 1 << 3,
+        survival: 1 << 2 | 1 << 3,
+    };
+
+    pub fn born(&self, neighbors: u8) -> bool {
+        self.birth & (1 << neighbors) != 0
+    }
+
+    pub fn survives(&self, neighbors: u8) -> bool {
+        self.survival & (1 << neighbors) != 0
+    }
+
+    /// Name of the matching entry in [`PRESETS`], if any.
+    pub fn name(&self) -> Option<&'static str> {
+        PRESETS
+            .iter()
+            .find(|(_, rs)| rs.parse::<Rule>().ok() == Some(*self))
+            .map(|(name, _)| *name)
+    }
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Rule::LIFE
+    }
+}
+
+fn parse_counts(digits: &str) -> Result<u16, ParseRuleError> {
+    let mut mask = 0u16;
+    for c in digits.chars() {
+        match c.to_digit(10) {
+            Some(n) if n <= 8 => mask |= 1 << n,
+            _ => return Err(ParseRuleError::InvalidDigit(c)),
+        }
+    }
+    Ok(mask)
+}
+
+impl FromStr for Rule {
+    type Err = ParseRuleError;
+
+    /// Accepts `B3/S23` (either order, any case), the legacy `23/3` survival/birth
+    /// notation, or a preset name such as `HighLife`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(ParseRuleError::Empty);
+        }
+        if let Some((_, rs)) = PRESETS
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(s))
+        {
+            return rs.parse();
+        }
+
+        let (first, second) = s
+            .split_once('/')
+            .ok_or_else(|| ParseRuleError::Malformed(s.to_string()))?;
+        let tagged = |part: &str| -> Option<(char, String)> {
+            let mut chars = part.chars();
+            let tag = chars.next()?.to_ascii_uppercase();
+            matches!(tag, 'B' | 'S').then(|| (tag, chars.collect()))
+        };
+
+        let (birth, survival) = match (tagged(first), tagged(second)) {
+            (Some(('B', b)), Some(('S', s))) | (Some(('S', s)), Some(('B', b))) => {
+                (parse_counts(&b)?, parse_counts(&s)?)
+            }
+            (None, None) => (parse_counts(second)?, parse_counts(first)?),
+            _ => return Err(ParseRuleError::Malformed(s.to_string())),
+        };
+        Ok(Rule { birth, survival })
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let counts = |mask: u16| -> String {
+            (0..=8)
+                .filter(|n| mask & (1 << n) != 0)
+                .map(|n| char::from(b'0' + n as u8))
+                .collect()
+        };
+        write!(f, "B{}/S{}", counts(self.birth), counts(self.survival))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_standard_notation() {
+        let rule: Rule = "B36/S23".parse().unwrap();
+        assert!(rule.born(3) && rule.born(6) && !rule.born(2));
+        assert!(rule.survives(2) && rule.survives(3) && !rule.survives(4));
+        assert_eq!(rule.to_string(), "B36/S23");
+    }
+
+    #[test]
+    fn parses_alternate_forms() {
+        assert_eq!("s23/b3".parse::<Rule>(), Ok(Rule::LIFE));
+        assert_eq!("23/3".parse::<Rule>(), Ok(Rule::LIFE));
+        assert_eq!("highlife".parse::<Rule>().unwrap().to_string(), "B36/S23");
+        assert_eq!("B2/S".parse::<Rule>().unwrap().to_string(), "B2/S");
+    }
+
+    #[test]
+    fn rejects_bad_rulestrings() {
+        assert_eq!("".parse::<Rule>(), Err(ParseRuleError::Empty));
+        assert_eq!(
+            "B39/S23".parse::<Rule>(),
+            Err(ParseRuleError::InvalidDigit('9'))
+        );
+        assert!(matches!(
+            "B3S23".parse::<Rule>(),
+            Err(ParseRuleError::Malformed(_))
+        ));
+        assert!(matches!(
+            "B3/B23".parse::<Rule>(),
+            Err(ParseRuleError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn presets_round_trip() {
+        for (name, rs) in PRESETS {
+            let rule: Rule = rs.parse().unwrap();
+            assert_eq!(rule.to_string(), *rs);
+            assert_eq!(rule.name(), Some(*name));
+        }
+    }
+}
@@ -0,0 +1,710 @@
+//! Versioned plain-text snapshot of the full simulation state. Unlike the pattern
+//! formats this keeps cell ages, so a restored session colors exactly as it did.
+//!
+//! ```text
+//! petri-session 2
+//! width 256
+//! height 256
+//! rule B3/S23
+//! ...
+//! cells
+//! 300*0 1 2 2*5 ...
+//! ```
+//!
+//! Cells are listed row-major as `count*age` runs (or a bare `age` for a run of one).
+//! An infinite plane is saved as `topology infinite` with an `engine` field naming
+//! the backend to restore it into. Its live cells can lie far apart, up to 2^60
+//! cells from the origin, so they are listed sparsely instead: each line gives a row, a starting column and
+//! runs from there on, as in `-70 20 3*1 2*0 9`. Version 1 listed every cell of the
+//! bounding box instead, from the corner given by an `origin x y` field.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::app::{App, MAX_GRID_SIZE, MAX_TICK_MS, MAX_ZOOM, MIN_TICK_MS, MIN_ZOOM};
+use crate::rule::{ParseRuleError, Rule};
+use crate::topology::Topology;
+use crate::universe::{Engine, MAX_COORD};
+
+const MAGIC: &str = "petri-session";
+pub const VERSION: u32 = 2;
+const LINE_WIDTH: usize = 70;
+/// Dead cells between two live ones in a row beyond which a sparse listing
+/// starts a new line rather than writing a run of zeros.
+const MAX_GAP: usize = 4;
+/// Most live cells a session may hold, as many as fit in the largest grid.
+const MAX_CELLS: usize = MAX_GRID_SIZE * MAX_GRID_SIZE;
+
+type Cell = (isize, isize, u16);
+
+pub struct Session {
+    /// Size of a finite grid, or of the live cells of an infinite plane.
+    pub width: usize,
+    pub height: usize,
+    pub rule: Rule,
+    /// `None` for an infinite plane.
+    pub topology: Option<Topology>,
+    pub engine: Engine,
+    pub generation: usize,
+    pub cursor_x: isize,
+    pub cursor_y: isize,
+    pub cursor_visible: bool,
+    pub viewport_x: isize,
+    pub viewport_y: isize,
+    pub zoom: i32,
+    pub tick_rate: Duration,
+    pub paused: bool,
+    /// Live cells with their ages, by row and then column.
+    pub cells: Vec<Cell>,
+}
+
+#[derive(Debug)]
+pub enum SessionError {
+    Io(io::Error),
+    NotASession,
+    UnsupportedVersion(String),
+    MissingField(&'static str),
+    InvalidField(String),
+    InvalidRule(ParseRuleError),
+    CellCount { expected: usize, found: usize },
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SessionError::Io(e) => write!(f, "{}", e),
+            SessionError::NotASession => write!(f, "not a petri session file"),
+            SessionError::UnsupportedVersion(v) => write!(f, "unsupported session version {}", v),
+            SessionError::MissingField(name) => write!(f, "missing field '{}'", name),
+            SessionError::InvalidField(line) => write!(f, "invalid field '{}'", line),
+            SessionError::InvalidRule(e) => write!(f, "{}", e),
+            SessionError::CellCount { expected, found } => {
+                write!(f, "expected {} cells, found {}", expected, found)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SessionError {}
+
+impl From<io::Error> for SessionError {
+    fn from(e: io::Error) -> Self {
+        SessionError::Io(e)
+    }
+}
+
+impl From<ParseRuleError> for SessionError {
+    fn from(e: ParseRuleError) -> Self {
+        SessionError::InvalidRule(e)
+    }
+}
+
+type Fields<'a> = HashMap<&'a str, &'a str>;
+
+fn field<'a>(fields: &Fields<'a>, name: &'static str) -> Result<&'a str, SessionError> {
+    fields
+        .get(name)
+        .copied()
+        .ok_or(SessionError::MissingField(name))
+}
+
+fn parsed<T: FromStr>(fields: &Fields, name: &'static str) -> Result<T, SessionError> {
+    let value = field(fields, name)?;
+    value
+        .parse()
+        .map_err(|_| SessionError::InvalidField(format!("{} {}", name, value)))
+}
+
+fn pair<T: FromStr>(fields: &Fields, name: &'static str) -> Result<(T, T), SessionError> {
+    let value = field(fields, name)?;
+    let invalid = || SessionError::InvalidField(format!("{} {}", name, value));
+    let [a, b] = value.split_whitespace().collect::<Vec<_>>()[..] else {
+        return Err(invalid());
+    };
+    Ok((
+        a.parse().map_err(|_| invalid())?,
+        b.parse().map_err(|_| invalid())?,
+    ))
+}
+
+/// Appends a run to `runs`, merging it into the last one if the ages match.
+fn push_run(runs: &mut Vec<(usize, u16)>, count: usize, age: u16) {
+    match runs.last_mut() {
+        _ if count == 0 => {}
+        Some((n, a)) if *a == age => *n += count,
+        _ => runs.push((count, age)),
+    }
+}
+
+fn run_token(count: usize, age: u16) -> String {
+    match count {
+        1 => age.to_string(),
+        n => format!("{}*{}", n, age),
+    }
+}
+
+/// A `count*age` or bare `age` token.
+fn parse_run(token: &str) -> Result<(usize, u16), SessionError> {
+    let invalid = || SessionError::InvalidField(token.to_string());
+    let (count, age) = match token.split_once('*') {
+        Some((n, age)) => (n.parse().map_err(|_| invalid())?, age),
+        None => (1, token),
+    };
+    Ok((count, age.parse().map_err(|_| invalid())?))
+}
+
+impl Session {
+    pub fn capture(app: &App) -> Self {
+        let (width, height) = app
+            .universe
+            .bounds()
+            .or_else(|| app.universe.bounding_box())
+            .map_or((0, 0), |r| (r.width, r.height));
+        let mut cells = app.universe.live_cells();
+        cells.sort_unstable_by_key(|&(x, y, _)| (y, x));
+        Self {
+            width,
+            height,
+            rule: app.universe.rule(),
+            topology: app.universe.topology(),
+            engine: app.universe.engine(),
+            generation: app.generation,
+            cursor_x: app.cursor_x,
+            cursor_y: app.cursor_y,
+            cursor_visible: app.cursor_visible,
+            viewport_x: app.viewport_x,
+            viewport_y: app.viewport_y,
+            zoom: app.zoom,
+            tick_rate: app.tick_rate,
+            paused: app.paused,
+            cells,
+        }
+    }
+
+    /// Replaces the simulation state of `app`, keeping its terminal view size.
+    pub fn restore(self, app: &mut App) {
+        if let Some(topology) = self.topology {
+            app.grid_size = (self.width, self.height);
+            app.universe = self.engine.create(app.grid_size);
+            app.universe.set_topology(topology);
+        } else {
+            app.universe = self.engine.create(app.grid_size);
+        }
+        app.universe.set_track_ages(app.show_ages);
+        app.universe.set_threads(app.threads);
+        // Rules with B0 are rejected when parsing infinite sessions
+        let _ = app.universe.set_rule(self.rule);
+        for &(x, y, age) in &self.cells {
+            app.universe.set(x, y, age);
+        }
+        app.generation = self.generation;
+        app.cursor_x = self.cursor_x;
+        app.cursor_y = self.cursor_y;
+        app.cursor_visible = self.cursor_visible;
+        app.viewport_x = self.viewport_x;
+        app.viewport_y = self.viewport_y;
+        app.zoom = self.zoom;
+        app.clamp_viewport();
+        app.tick_rate = self.tick_rate;
+        app.paused = self.paused;
+        app.placing = None;
+        app.seed = None;
+        app.history.clear();
+        app.timeline.clear();
+        if app.recording {
+            app.stop_recording();
+        }
+    }
+
+    pub fn write(&self) -> String {
+        let mut out = format!("{} {}\n", MAGIC, VERSION);
+        out.push_str(&format!("width {}\n", self.width));
+        out.push_str(&format!("height {}\n", self.height));
+        out.push_str(&format!("rule {}\n", self.rule));
+        match self.topology {
+            Some(topology) => out.push_str(&format!("topology {}\n", topology)),
+            None => {
+                out.push_str("topology infinite\n");
+                out.push_str(&format!("engine {}\n", self.engine));
+            }
+        }
+        out.push_str(&format!("generation {}\n", self.generation));
+        out.push_str(&format!("cursor {} {}\n", self.cursor_x, self.cursor_y));
+        out.push_str(&format!("cursor_visible {}\n", self.cursor_visible));
+        out.push_str(&format!(
+            "viewport {} {}\n",
+            self.viewport_x, self.viewport_y
+        ));
+        out.push_str(&format!("zoom {}\n", self.zoom));
+        out.push_str(&format!("tick_ms {}\n", self.tick_rate.as_millis()));
+        out.push_str(&format!("paused {}\n", self.paused));
+        out.push_str("cells\n");
+
+        match self.topology {
+            Some(_) => self.write_dense(&mut out),
+            None => self.write_sparse(&mut out),
+        }
+        out
+    }
+
+    /// Every cell of the grid, dead or alive, as runs wrapped at `LINE_WIDTH`.
+    fn write_dense(&self, out: &mut String) {
+        let mut runs: Vec<(usize, u16)> = Vec::new();
+        let mut next = 0;
+        for &(x, y, age) in &self.cells {
+            let index = y as usize * self.width + x as usize;
+            push_run(&mut runs, index - next, 0);
+            push_run(&mut runs, 1, age);
+            next = index + 1;
+        }
+        push_run(&mut runs, self.width * self.height - next, 0);
+
+        let mut line = String::new();
+        for (count, age) in runs {
+            let token = run_token(count, age);
+            if !line.is_empty() && line.len() + token.len() + 1 > LINE_WIDTH {
+                out.push_str(&line);
+                out.push('\n');
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        out.push_str(&line);
+        out.push('\n');
+    }
+
+    /// Live cells as lines of `y x runs...`, starting a new line at long gaps
+    /// and at `LINE_WIDTH`.
+    fn write_sparse(&self, out: &mut String) {
+        for row in self.cells.chunk_by(|a, b| a.1 == b.1) {
+            let y = row[0].1;
+            // Runs with their starting column
+            let mut runs: Vec<(isize, usize, u16)> = Vec::new();
+            for &(x, _, age) in row {
+                match runs.last_mut() {
+                    Some((start, count, a)) if *start + *count as isize == x && *a == age => {
+                        *count += 1;
+                    }
+                    Some(&mut (start, count, _)) => {
+                        let end = start + count as isize;
+                        if x > end && (x - end) as usize <= MAX_GAP {
+                            runs.push((end, (x - end) as usize, 0));
+                        }
+                        runs.push((x, 1, age));
+                    }
+                    None => runs.push((x, 1, age)),
+                }
+            }
+            let mut line = String::new();
+            let mut end = None;
+            for (x, count, age) in runs {
+                let token = run_token(count, age);
+                if end != Some(x) || line.len() + token.len() + 1 > LINE_WIDTH {
+                    if !line.is_empty() {
+                        out.push_str(&line);
+                        out.push('\n');
+                    }
+                    line = format!("{} {}", y, x);
+                }
+                line.push(' ');
+                line.push_str(&token);
+                end = Some(x + count as isize);
+            }
+            out.push_str(&line);
+            out.push('\n');
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, SessionError> {
+        let mut lines = text.lines();
+        let version = lines
+            .next()
+            .and_then(|l| l.strip_prefix(MAGIC))
+            .ok_or(SessionError::NotASession)?
+            .trim();
+        let version: u32 = match version.parse() {
+            Ok(v @ 1..=VERSION) => v,
+            _ => return Err(SessionError::UnsupportedVersion(version.to_string())),
+        };
+
+        let mut fields = HashMap::new();
+        for line in lines.by_ref() {
+            let line = line.trim();
+            if line == "cells" {
+                break;
+            }
+            if let Some((key, value)) = line.split_once(' ') {
+                fields.insert(key, value.trim());
+            } else if !line.is_empty() {
+                return Err(SessionError::InvalidField(line.to_string()));
+            }
+        }
+
+        let width: usize = parsed(&fields, "width")?;
+        let height: usize = parsed(&fields, "height")?;
+        let (cursor_x, cursor_y) = pair(&fields, "cursor")?;
+        let (viewport_x, viewport_y) = pair(&fields, "viewport")?;
+        let tick_ms = parsed(&fields, "tick_ms")?;
+        if !(MIN_TICK_MS..=MAX_TICK_MS).contains(&tick_ms) {
+            return Err(SessionError::InvalidField(format!("tick_ms {}", tick_ms)));
+        }
+
+        // Absent from sessions written before topologies were configurable
+        let topology = match fields.get("topology") {
+            Some(&"infinite") => None,
+            Some(name) => Some(name.parse().map_err(SessionError::InvalidField)?),
+            None => Some(Topology::Torus),
+        };
+        let engine = match (fields.get("engine"), topology) {
+            (None, Some(_)) => Engine::Grid,
+            // Infinite sessions from before HashLife was added used tiles
+            (None, None) => Engine::Tiled,
+            (Some(name), _) => name.parse().map_err(SessionError::InvalidField)?,
+        };
+        if (engine == Engine::Grid) != topology.is_some() {
+            return Err(SessionError::InvalidField(format!("engine {}", engine)));
+        }
+        let rule: Rule = field(&fields, "rule")?.parse()?;
+        // Checked before the cells, which a crafted size could make unbounded
+        if topology.is_some() && width > MAX_GRID_SIZE {
+            return Err(SessionError::InvalidField(format!("width {}", width)));
+        }
+        if topology.is_some() && height > MAX_GRID_SIZE {
+            return Err(SessionError::InvalidField(format!("height {}", height)));
+        }
+        if topology.is_none() && rule.born(0) {
+            return Err(SessionError::InvalidField(format!("rule {}", rule)));
+        }
+        let origin = match topology {
+            Some(_) => Some((0, 0)),
+            None if version == 1 => Some(pair(&fields, "origin")?),
+            None => None,
+        };
+        let cells = match origin {
+            Some(origin) => {
+                let tokens = lines.flat_map(str::split_whitespace);
+                parse_dense(tokens, origin, width, height)?
+            }
+            None => parse_sparse(lines)?,
+        };
+        if topology.is_some() {
+            let in_grid = |x: isize, y: isize, w: usize, h: usize| {
+                (0..w as isize).contains(&x) && (0..h as isize).contains(&y)
+            };
+            if !in_grid(cursor_x, cursor_y, width, height) {
+                return Err(SessionError::InvalidField(format!(
+                    "cursor {} {}",
+                    cursor_x, cursor_y
+                )));
+            }
+            // A view scrolled across the edges of a wrapping grid lies within
+            // two laps of the origin; see `App::clamp_viewport`
+            if !in_grid(viewport_x, viewport_y, 2 * width.max(1), 2 * height.max(1)) {
+                return Err(SessionError::InvalidField(format!(
+                    "viewport {} {}",
+                    viewport_x, viewport_y
+                )));
+            }
+        }
+
+        Ok(Self {
+            width,
+            height,
+            rule,
+            topology,
+            engine,
+            generation: parsed(&fields, "generation")?,
+            cursor_x,
+            cursor_y,
+            cursor_visible: parsed(&fields, "cursor_visible")?,
+            viewport_x,
+            viewport_y,
+            zoom: parsed::<i32>(&fields, "zoom")?.clamp(MIN_ZOOM, MAX_ZOOM),
+            tick_rate: Duration::from_millis(tick_ms),
+            paused: parsed(&fields, "paused")?,
+            cells,
+        })
+    }
+}
+
+/// Cells listed row-major from `origin` across a `width` x `height` area.
+fn parse_dense<'a>(
+    tokens: impl Iterator<Item = &'a str>,
+    origin: (isize, isize),
+    width: usize,
+    height: usize,
+) -> Result<Vec<Cell>, SessionError> {
+    let expected = width
+        .checked_mul(height)
+        .ok_or_else(|| SessionError::InvalidField(format!("width {}", width)))?;
+    let mut cells = Vec::new();
+    let mut next: usize = 0;
+    for token in tokens {
+        let (count, age) = parse_run(token)?;
+        if count > expected - next {
+            return Err(SessionError::CellCount {
+                expected,
+                found: next.saturating_add(count),
+            });
+        }
+        if age > 0 {
+            if count > MAX_CELLS - cells.len() {
+                return Err(SessionError::InvalidField(token.to_string()));
+            }
+            cells.extend((next..next + count).map(|i| {
+                let (x, y) = ((i % width) as isize, (i / width) as isize);
+                (origin.0 + x, origin.1 + y, age)
+            }));
+        }
+        next += count;
+    }
+    if next != expected {
+        return Err(SessionError::CellCount {
+            expected,
+            found: next,
+        });
+    }
+    Ok(cells)
+}
+
+/// Live cells listed as lines of `y x runs...`.
+fn parse_sparse<'a>(lines: impl Iterator<Item = &'a str>) -> Result<Vec<Cell>, SessionError> {
+    let mut cells = Vec::new();
+    for line in lines {
+        let invalid = || SessionError::InvalidField(line.to_string());
+        let mut tokens = line.split_whitespace();
+        let Some(y) = tokens.next() else {
+            continue;
+        };
+        let in_range = |v: &isize| (-MAX_COORD..MAX_COORD).contains(v);
+        let y = y.parse().ok().filter(in_range).ok_or_else(invalid)?;
+        let mut x = tokens
+            .next()
+            .and_then(|x| x.parse().ok())
+            .filter(in_range)
+            .ok_or_else(invalid)?;
+        for token in tokens {
+            let (count, age) = parse_run(token)?;
+            let end = isize::try_from(count)
+                .ok()
+                .and_then(|n| x.checked_add(n))
+                .filter(|&end| end <= MAX_COORD)
+                .ok_or_else(invalid)?;
+            if age > 0 {
+                if count > MAX_CELLS - cells.len() {
+                    return Err(invalid());
+                }
+                cells.extend((x..end).map(|x| (x, y, age)));
+            }
+            x = end;
+        }
+    }
+    Ok(cells)
+}
+
+pub fn save(app: &App, path: &Path) -> io::Result<()> {
+    fs::write(path, Session::capture(app).write())
+}
+
+pub fn load(path: &Path) -> Result<Session, SessionError> {
+    Session::parse(&fs::read_to_string(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Grid;
+    use crate::universe::Universe;
+
+    fn sample_app() -> App {
+        let mut grid = Grid::new(12, 7);
+        grid.rule = "B36/S23".parse().unwrap();
+        grid.topology = Topology::KleinBottle;
+        for (i, age) in [(3, 1), (4, 7), (5, 7), (30, 300), (83, u16::MAX)] {
+            grid.set(i % 12, i / 12, age);
+        }
+        let mut app = App::with_universe(Box::new(grid), 40, 20);
+        app.generation = 4242;
+        app.cursor_x = 11;
+        app.cursor_y = 6;
+        app.cursor_visible = true;
+        app.zoom = 3;
+        app.tick_rate = Duration::from_millis(35);
+        app.paused = false;
+        app
+    }
+
+    #[test]
+    fn round_trips_app_state() {
+        let app = sample_app();
+        let text = Session::capture(&app).write();
+        assert!(text.lines().all(|l| l.len() <= LINE_WIDTH));
+
+        let mut restored = App::new(256, 256, 40, 20);
+        Session::parse(&text).unwrap().restore(&mut restored);
+        assert_eq!(restored.universe.bounds(), app.universe.bounds());
+        assert_eq!(restored.universe.live_cells(), app.universe.live_cells());
+        assert_eq!(restored.universe.rule(), app.universe.rule());
+        assert_eq!(restored.universe.topology(), Some(Topology::KleinBottle));
+        assert_eq!(restored.generation, 4242);
+        assert_eq!((restored.cursor_x, restored.cursor_y), (11, 6));
+        assert!(restored.cursor_visible);
+        assert_eq!(restored.zoom, 3);
+        assert_eq!(restored.tick_rate, Duration::from_millis(35));
+        assert!(!restored.paused);
+    }
+
+    #[test]
+    fn round_trips_a_view_across_wrapping_edges() {
+        let mut app = App::new(64, 64, 10, 10);
+        app.universe.set_topology(Topology::KleinBottle);
+        app.viewport_x = 124;
+        app.viewport_y = 100;
+        let text = Session::capture(&app).write();
+        let mut restored = App::new(16, 16, 10, 10);
+        Session::parse(&text).unwrap().restore(&mut restored);
+        assert_eq!((restored.viewport_x, restored.viewport_y), (124, 100));
+    }
+
+    #[test]
+    fn round_trips_infinite_plane() {
+        let mut app = App::with_universe(Engine::HashLife.create((0, 0)), 40, 20);
+        let far = 1 << 40;
+        for (x, y, age) in [(-500, 3, 2), (-498, 4, 1), (20, -70, 9), (far, far, 1)] {
+            app.universe.set(x, y, age);
+        }
+        app.cursor_x = -1000;
+        app.viewport_y = -60;
+        let text = Session::capture(&app).write();
+        // Only live cells are listed, however far apart
+        assert!(
+            text.ends_with("cells\n-70 20 1\n3 -500 1\n4 -498 1\n1099511627776 1099511627776 1\n")
+        );
+
+        let mut restored = App::new(16, 16, 40, 20);
+        Session::parse(&text).unwrap().restore(&mut restored);
+        assert_eq!(restored.universe.engine(), Engine::HashLife);
+        let mut cells = restored.universe.live_cells();
+        cells.sort();
+        // HashLife does not keep ages
+        assert_eq!(
+            cells,
+            vec![(-500, 3, 1), (-498, 4, 1), (20, -70, 1), (far, far, 1)]
+        );
+        assert_eq!((restored.cursor_x, restored.viewport_y), (-1000, -60));
+    }
+
+    #[test]
+    fn sparse_rows_split_at_long_gaps() {
+        let mut app = App::with_universe(Engine::Tiled.create((0, 0)), 40, 20);
+        for (x, age) in [(0, 1), (1, 1), (2, 5), (5, 5), (20, 2)] {
+            app.universe.set(x, -3, age);
+        }
+        let session = Session::capture(&app);
+        let text = session.write();
+        assert!(text.ends_with("cells\n-3 0 2*1 5 2*0 5\n-3 20 2\n"));
+        assert_eq!(Session::parse(&text).unwrap().cells, session.cells);
+    }
+
+    #[test]
+    fn reads_version_1_infinite_sessions() {
+        let text = "petri-session 1\nwidth 3\nheight 2\nrule B3/S23\n\
+            topology infinite\norigin -10 5\ngeneration 7\ncursor 0 0\n\
+            cursor_visible false\nviewport 0 0\nzoom 1\ntick_ms 100\n\
+            paused true\ncells\n2*0 4 3*0\n";
+        let session = Session::parse(text).unwrap();
+        assert_eq!(session.engine, Engine::Tiled);
+        assert_eq!(session.cells, vec![(-8, 5, 4)]);
+    }
+
+    #[test]
+    fn rejects_bad_sessions() {
+        let text = Session::capture(&sample_app()).write();
+        assert!(matches!(
+            Session::parse("x = 3, y = 3\n"),
+            Err(SessionError::NotASession)
+        ));
+        assert!(matches!(
+            Session::parse(&text.replace("petri-session 2", "petri-session 99")),
+            Err(SessionError::UnsupportedVersion(_))
+        ));
+        assert!(matches!(
+            Session::parse(&text.replace("width 12", "width 13")),
+            Err(SessionError::CellCount {
+                expected: 91,
+                found: 84
+            })
+        ));
+        // Sizes and counts that would overflow or exhaust memory
+        assert!(matches!(
+            Session::parse(&text.replace("width 12", "width 99999999999")),
+            Err(SessionError::InvalidField(f)) if f == "width 99999999999"
+        ));
+        let huge = "topology infinite\norigin 0 0\nengine tiled";
+        let v1 = text
+            .replace("petri-session 2", "petri-session 1")
+            .replace("topology klein", huge);
+        assert!(matches!(
+            Session::parse(
+                &v1.replace("width 12", "width 99999999999")
+                    .replace("height 7", "height 99999999999")
+            ),
+            Err(SessionError::InvalidField(f)) if f == "width 99999999999"
+        ));
+        assert!(matches!(
+            Session::parse(
+                &v1.replace("width 12", "width 9999999999")
+                    .replace("height 7", "height 1")
+                    .replace("cells\n", "cells\n9999999999*1\n")
+            ),
+            Err(SessionError::InvalidField(f)) if f == "9999999999*1"
+        ));
+        assert!(matches!(
+            Session::parse(&text.replace("cells\n", "cells\n18446744073709551615*0 ")),
+            Err(SessionError::CellCount { expected: 84, .. })
+        ));
+        assert!(matches!(
+            Session::parse(&format!(
+                "{}cells\n0 0 9223372036854775807*1\n",
+                text.split("cells\n")
+                    .next()
+                    .unwrap()
+                    .replace("topology klein", huge)
+            )),
+            Err(SessionError::InvalidField(f)) if f.starts_with("0 0 ")
+        ));
+        let far = format!(
+            "{}cells\n0 {} 1\n",
+            text.split("cells\n").next().unwrap().replace(
+                "petri-session 2\nwidth 12\nheight 7\nrule B36/S23\ntopology klein",
+                "petri-session 2\nwidth 0\nheight 0\nrule B3/S23\ntopology infinite\nengine hashlife"
+            ),
+            MAX_COORD
+        );
+        assert!(matches!(
+            Session::parse(&far),
+            Err(SessionError::InvalidField(f)) if f.starts_with("0 ")
+        ));
+        assert!(Session::parse(&far.replace(&MAX_COORD.to_string(), "-5")).is_ok());
+        assert!(matches!(
+            Session::parse(&text.replace("tick_ms 35", "tick_ms 0")),
+            Err(SessionError::InvalidField(f)) if f == "tick_ms 0"
+        ));
+        assert!(matches!(
+            Session::parse(&text.replace("viewport 0 0", "viewport 24 0")),
+            Err(SessionError::InvalidField(f)) if f == "viewport 24 0"
+        ));
+        assert!(matches!(
+            Session::parse(&text.replace("zoom 3\n", "")),
+            Err(SessionError::MissingField("zoom"))
+        ));
+    }
+}
@@ -0,0 +1,443 @@
+//! A ring buffer of recent generations for rewinding the simulation.
+//!
+//! Every `KEYFRAME_INTERVAL` frames the timeline stores a keyframe holding all
+//! live cells and their ages; the frames in between store only the cells born
+//! and the cells that died since the frame before. Cell lists are sorted and
+//! varint-encoded as offsets from the previous cell, so a delta costs a couple
+//! of bytes per changed cell. Ages between keyframes are rebuilt by aging the
+//! survivors, which matches the engines exactly when each frame is one
+//! generation apart. Packed grids are diffed a word at a time against the
+//! frame before, so a delta costs little more than the cells that changed.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::universe::Universe;
+
+/// Frames kept when the app starts.
+pub const DEFAULT_FRAMES: usize = 1000;
+const KEYFRAME_INTERVAL: usize = 32;
+/// Encoded bytes kept before the oldest frames are dropped.
+const MAX_BYTES: usize = 64 << 20;
+/// Largest population recorded from a list of live cells; listing more every
+/// frame would slow the simulation down more than rewinding is worth. Above it
+/// recording pauses until the population shrinks again.
+const MAX_CELLS: usize = 1 << 16;
+/// Largest population recorded from packed rows, which only list their cells
+/// on keyframes.
+const MAX_PACKED_CELLS: usize = 1 << 18;
+
+type Cell = (isize, isize, u16);
+
+/// Live cells of the newest frame, to diff the next one against.
+enum Last {
+    Cells(HashSet<(isize, isize)>),
+    /// Packed rows and the words in each, as given by [`Universe::packed_rows`].
+    Words(Vec<u64>, usize),
+}
+
+impl Last {
+    /// Whether a universe with these packed rows (if any) can be diffed against it.
+    fn fits(&self, packed: Option<(&[u64], usize)>) -> bool {
+        match (self, packed) {
+            (Last::Cells(_), None) => true,
+            (Last::Words(old, old_stride), Some((words, stride))) => {
+                old.len() == words.len() && *old_stride == stride
+            }
+            _ => false,
+        }
+    }
+}
+
+struct Frame {
+    generation: usize,
+    keyframe: bool,
+    data: Vec<u8>,
+}
+
+/// Recent generations of one run, oldest first.
+pub struct Timeline {
+    frames: VecDeque<Frame>,
+    capacity: usize,
+    bytes: usize,
+    last: Last,
+    /// Frame being shown while rewound; `None` when following the simulation.
+    position: Option<usize>,
+}
+
+impl Timeline {
+    /// A timeline holding at most `capacity` frames; 0 disables it.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            frames: VecDeque::new(),
+            capacity,
+            bytes: 0,
+            last: Last::Cells(HashSet::new()),
+            position: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Index of the frame on screen while rewound.
+    pub fn position(&self) -> Option<usize> {
+        self.position
+    }
+
+    pub fn newest_generation(&self) -> Option<usize> {
+        self.frames.back().map(|f| f.generation)
+    }
+
+    pub fn generation(&self, index: usize) -> usize {
+        self.frames[index].generation
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.bytes = 0;
+        self.last = Last::Cells(HashSet::new());
+        self.position = None;
+    }
+
+    /// Appends the state of `universe` at `generation`, first discarding any
+    /// frames at or after it, such as the future of a rewound run. A population
+    /// too big to record empties the timeline, as later deltas would have no base.
+    pub fn record(&mut self, universe: &dyn Universe, generation: usize) {
+        self.position = None;
+        if self.capacity == 0 {
+            return;
+        }
+        let packed = universe.packed_rows();
+        let limit = if packed.is_some() {
+            MAX_PACKED_CELLS
+        } else {
+            MAX_CELLS
+        };
+        if universe.population() > limit {
+            self.clear();
+            return;
+        }
+        let mut truncated = false;
+        while self
+            .frames
+            .back()
+            .is_some_and(|f| f.generation >= generation)
+        {
+            let frame = self.frames.pop_back().unwrap();
+            self.bytes -= frame.data.len();
+            truncated = true;
+        }
+        if truncated {
+            self.last = match self.frames.len() {
+                0 => Last::Cells(HashSet::new()),
+                n => {
+                    let cells = self.cells(n - 1).into_iter().map(|(x, y, _)| (x, y));
+                    match packed {
+                        Some((words, stride)) => {
+                            let mut old = vec![0; words.len()];
+                            for (x, y) in cells {
+                                old[y as usize * stride + x as usize / 64] |= 1 << (x % 64);
+                            }
+                            Last::Words(old, stride)
+                        }
+                        None => Last::Cells(cells.collect()),
+                    }
+                }
+            };
+        }
+
+        let since_keyframe = self.frames.iter().rev().take_while(|f| !f.keyframe).count();
+        let mut data = Vec::new();
+        let keyframe = self.frames.is_empty()
+            || since_keyframe + 1 >= KEYFRAME_INTERVAL
+            || !self.last.fits(packed);
+        let current = if keyframe {
+            let mut cells = universe.live_cells();
+            cells.sort_unstable_by_key(|&(x, y, _)| (y, x));
+            encode(&mut data, cells.iter().map(|&(x, y, _)| (x, y)));
+            for &(_, _, age) in &cells {
+                put(&mut data, age as u64);
+            }
+            match packed {
+                Some((words, stride)) => Last::Words(words.to_vec(), stride),
+                None => Last::Cells(cells.iter().map(|&(x, y, _)| (x, y)).collect()),
+            }
+        } else {
+            match (&self.last, packed) {
+                (Last::Words(old, _), Some((words, stride))) => {
+                    let (mut born, mut died) = (Vec::new(), Vec::new());
+                    for (k, (&new, &old)) in words.iter().zip(old).enumerate() {
+                        if new != old {
+                            push_bits(&mut born, new & !old, k, stride);
+                            push_bits(&mut died, old & !new, k, stride);
+                        }
+                    }
+                    encode(&mut data, born.into_iter());
+                    encode(&mut data, died.into_iter());
+                    Last::Words(words.to_vec(), stride)
+                }
+                (Last::Cells(old), _) => {
+                    let mut cells: Vec<_> = universe
+                        .live_cells()
+                        .into_iter()
+                        .map(|(x, y, _)| (x, y))
+                        .collect();
+                    cells.sort_unstable_by_key(|&(x, y)| (y, x));
+                    let current: HashSet<_> = cells.iter().copied().collect();
+                    let mut died: Vec<_> = old.difference(&current).copied().collect();
+                    died.sort_unstable_by_key(|&(x, y)| (y, x));
+                    encode(&mut data, cells.into_iter().filter(|p| !old.contains(p)));
+                    encode(&mut data, died.into_iter());
+                    Last::Cells(current)
+                }
+                (Last::Words(..), None) => unreachable!("checked by `fits`"),
+            }
+        };
+        self.last = current;
+        let frame = Frame {
+            generation,
+            keyframe,
+            data,
+        };
+        self.bytes += frame.data.len();
+        self.frames.push_back(frame);
+
+        // Drop whole keyframe groups so every remaining delta has its base
+        while self.frames.len() > self.capacity || self.bytes > MAX_BYTES {
+            let Some(oldest) = self.frames.pop_front() else {
+                break;
+            };
+            self.bytes -= oldest.data.len();
+            while let Some(next) = self.frames.front().filter(|f| !f.keyframe) {
+                self.bytes -= next.data.len();
+                self.frames.pop_front();
+            }
+        }
+        if self.frames.is_empty() {
+            self.last = Last::Cells(HashSet::new());
+        }
+    }
+
+    /// Moves to frame `index`, returning its live cells.
+    pub fn seek(&mut self, index: usize) -> Vec<Cell> {
+        self.position = Some(index);
+        self.cells(index)
+    }
+
+    /// Rebuilds the live cells of frame `index` from the keyframe before it.
+    fn cells(&self, index: usize) -> Vec<Cell> {
+        let start = (0..=index)
+            .rev()
+            .find(|&i| self.frames[i].keyframe)
+            .expect("the oldest frame is a keyframe");
+        let mut reader = Reader::new(&self.frames[start].data);
+        let positions = reader.cells();
+        let mut cells: HashMap<_, _> = positions
+            .into_iter()
+            .map(|p| (p, reader.next() as u16))
+            .collect();
+        for i in start + 1..=index {
+            let elapsed = self.frames[i].generation - self.frames[i - 1].generation;
+            let elapsed = elapsed.min(u16::MAX as usize) as u16;
+            for age in cells.values_mut() {
+                *age = age.saturating_add(elapsed);
+            }
+            let mut reader = Reader::new(&self.frames[i].data);
+            let born = reader.cells();
+            for p in reader.cells() {
+                cells.remove(&p);
+            }
+            for p in born {
+                cells.insert(p, 1);
+            }
+        }
+        let mut cells: Vec<_> = cells.into_iter().map(|((x, y), age)| (x, y, age)).collect();
+        cells.sort_unstable_by_key(|&(x, y, _)| (y, x));
+        cells
+    }
+}
+
+fn put(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn zigzag(value: isize) -> u64 {
+    ((value << 1) ^ (value >> (isize::BITS - 1))) as u64
+}
+
+fn unzigzag(value: u64) -> isize {
+    (value >> 1) as isize ^ -((value & 1) as isize)
+}
+
+/// Appends the cells of the set bits of word `k` of packed rows `stride` words wide.
+fn push_bits(out: &mut Vec<(isize, isize)>, mut word: u64, k: usize, stride: usize) {
+    while word != 0 {
+        let bit = word.trailing_zeros() as usize;
+        word &= word - 1;
+        out.push((((k % stride) * 64 + bit) as isize, (k / stride) as isize));
+    }
+}
+
+/// Writes a count followed by each position as an offset from the one before.
+fn encode(out: &mut Vec<u8>, cells: impl Iterator<Item = (isize, isize)>) {
+    let cells: Vec<_> = cells.collect();
+    put(out, cells.len() as u64);
+    let (mut px, mut py) = (0, 0);
+    for (x, y) in cells {
+        put(out, zigzag(y - py));
+        put(out, zigzag(x - px));
+        (px, py) = (x, y);
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn next(&mut self) -> u64 {
+        let mut value = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.data[self.pos];
+            self.pos += 1;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte < 0x80 {
+                return value;
+            }
+            shift += 7;
+        }
+    }
+
+    fn cells(&mut self) -> Vec<(isize, isize)> {
+        let count = self.next() as usize;
+        let (mut x, mut y) = (0, 0);
+        (0..count)
+            .map(|_| {
+                y += unzigzag(self.next());
+                x += unzigzag(self.next());
+                (x, y)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Grid;
+    use crate::plane::Plane;
+    use crate::universe::Region;
+
+    fn r_pentomino() -> Plane {
+        let mut plane = Plane::new();
+        for (x, y) in [(1, 0), (2, 0), (0, 1), (1, 1), (1, 2)] {
+            plane.set(x, y, 1);
+        }
+        plane
+    }
+
+    #[test]
+    fn rebuilds_every_generation_with_ages() {
+        let mut plane = r_pentomino();
+        let mut timeline = Timeline::new(DEFAULT_FRAMES);
+        let mut expected = Vec::new();
+        for generation in 0..100 {
+            timeline.record(&plane, generation);
+            let mut cells = plane.live_cells();
+            cells.sort_unstable_by_key(|&(x, y, _)| (y, x));
+            expected.push(cells);
+            plane.step();
+        }
+        assert_eq!(timeline.len(), 100);
+        for (i, cells) in expected.iter().enumerate() {
+            assert_eq!(&timeline.seek(i), cells, "generation {}", i);
+        }
+    }
+
+    #[test]
+    fn diffs_packed_grids_word_by_word() {
+        let mut grid = Grid::new(100, 70);
+        let all = Region {
+            x: 0,
+            y: 0,
+            width: 100,
+            height: 70,
+        };
+        grid.randomize(all, 0.4, &mut fastrand::Rng::with_seed(3));
+        let mut timeline = Timeline::new(DEFAULT_FRAMES);
+        let mut expected = Vec::new();
+        for generation in 0..40 {
+            timeline.record(&grid, generation);
+            expected.push(grid.live_cells());
+            grid.step();
+        }
+        for (i, cells) in expected.iter().enumerate() {
+            assert_eq!(&timeline.seek(i), cells, "generation {}", i);
+        }
+
+        // Resuming from a rewound frame diffs against that frame
+        let mut rewound = Grid::new(100, 70);
+        for (x, y, age) in timeline.seek(20) {
+            rewound.set(x, y, age);
+        }
+        rewound.step();
+        timeline.record(&rewound, 21);
+        assert_eq!(timeline.seek(21), expected[21]);
+    }
+
+    #[test]
+    fn drops_whole_keyframe_groups() {
+        let mut plane = r_pentomino();
+        let mut timeline = Timeline::new(40);
+        for generation in 0..100 {
+            timeline.record(&plane, generation);
+            plane.step();
+        }
+        // Groups of 32 frames start at 0, 32, 64 and 96
+        assert_eq!(timeline.len(), 36);
+        assert_eq!(timeline.generation(0), 64);
+        assert_eq!(timeline.seek(35).len(), {
+            let mut plane = r_pentomino();
+            for _ in 0..99 {
+                plane.step();
+            }
+            plane.population()
+        });
+    }
+
+    #[test]
+    fn recording_an_earlier_generation_discards_the_future() {
+        let mut plane = r_pentomino();
+        let mut timeline = Timeline::new(DEFAULT_FRAMES);
+        for generation in 0..50 {
+            timeline.record(&plane, generation);
+            plane.step();
+        }
+        let cells = timeline.seek(10);
+        let mut rewound = Plane::new();
+        for &(x, y, age) in &cells {
+            rewound.set(x, y, age);
+        }
+        timeline.record(&rewound, 10);
+        rewound.step();
+        timeline.record(&rewound, 11);
+        assert_eq!(timeline.len(), 12);
+        assert_eq!(timeline.newest_generation(), Some(11));
+        assert_eq!(timeline.position(), None);
+    }
+}
@@ -0,0 +1,123 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// How the edges of a finite grid are joined.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Topology {
+    /// Cells beyond the edges are permanently dead.
+    Plane,
+    /// Opposite edges are joined.
+    #[default]
+    Torus,
+    /// Left/right edges are joined; crossing the top/bottom edge mirrors x.
+    KleinBottle,
+    /// Both pairs of edges are joined with a twist (the real projective plane).
+    CrossSurface,
+}
+
+pub const ALL: &[Topology] = &[
+    Topology::Torus,
+    Topology::Plane,
+    Topology::KleinBottle,
+    Topology::CrossSurface,
+];
+
+impl Topology {
+    /// Maps a possibly out-of-range coordinate onto a `width` × `height` grid,
+    /// or `None` if it falls off a bounded plane.
+    pub fn wrap(self, x: isize, y: isize, width: usize, height: usize) -> Option<(usize, usize)> {
+        let (w, h) = (width as isize, height as isize);
+        if (0..w).contains(&x) && (0..h).contains(&y) {
+            return Some((x as usize, y as usize));
+        }
+
+        let (mut wx, mut wy) = (x.rem_euclid(w), y.rem_euclid(h));
+        // An odd number of trips across a twisted edge leaves the cell mirrored
+        let crossed_x = x.div_euclid(w) % 2 != 0;
+        let crossed_y = y.div_euclid(h) % 2 != 0;
+        match self {
+            Topology::Plane => return None,
+            Topology::Torus => {}
+            Topology::KleinBottle => {
+                if crossed_y {
+                    wx = w - 1 - wx;
+                }
+            }
+            Topology::CrossSurface => {
+                if crossed_y {
+                    wx = w - 1 - wx;
+                }
+                if crossed_x {
+                    wy = h - 1 - wy;
+                }
+            }
+        }
+        Some((wx as usize, wy as usize))
+    }
+
+    pub fn next(self) -> Topology {
+        let idx = ALL.iter().position(|&t| t == self).unwrap_or(0);
+        ALL[(idx + 1) % ALL.len()]
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Topology::Plane => "plane",
+            Topology::Torus => "torus",
+            Topology::KleinBottle => "klein",
+            Topology::CrossSurface => "cross",
+        }
+    }
+}
+
+impl fmt::Display for Topology {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Topology {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ALL.iter()
+            .copied()
+            .find(|t| t.name().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| {
+                let names: Vec<_> = ALL.iter().map(|t| t.name()).collect();
+                format!("unknown topology '{}' (expected {})", s, names.join(", "))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn in_range_coordinates_are_unchanged() {
+        for &topology in ALL {
+            assert_eq!(topology.wrap(3, 4, 10, 8), Some((3, 4)));
+        }
+    }
+
+    #[test]
+    fn edges_join_per_topology() {
+        assert_eq!(Topology::Plane.wrap(-1, 4, 10, 8), None);
+        assert_eq!(Topology::Torus.wrap(-1, 8, 10, 8), Some((9, 0)));
+        // Klein bottle: straight across left/right, mirrored across top/bottom
+        assert_eq!(Topology::KleinBottle.wrap(10, 2, 10, 8), Some((0, 2)));
+        assert_eq!(Topology::KleinBottle.wrap(2, -1, 10, 8), Some((7, 7)));
+        // Cross-surface: mirrored across both
+        assert_eq!(Topology::CrossSurface.wrap(10, 2, 10, 8), Some((0, 5)));
+        assert_eq!(Topology::CrossSurface.wrap(2, 8, 10, 8), Some((7, 0)));
+    }
+
+    #[test]
+    fn parses_names() {
+        for &topology in ALL {
+            assert_eq!(topology.name().parse::<Topology>(), Ok(topology));
+        }
+        assert!("sphere".parse::<Topology>().is_err());
+    }
+}
@@ -1,7 +1,10 @@
-use crate::app::App;
-use crate::patterns;
+use std::collections::HashSet;
+
+use crate::app::{App, LIBRARY_COLUMNS};
+use crate::library;
+use crate::minimap::{self, Minimap};
 use ratatui::prelude::*;
-use ratatui::widgets::{Block, Borders, Paragraph};
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap};
 
 pub fn age_color(age: u16) -> Color {
     match age {
@@ -15,105 +18,176 @@ pub fn age_color(age: u16) -> Color {
     }
 }
 
+const TILE_TINT: Color = Color::Rgb(30, 30, 70);
+const GHOST_COLOR: Color = Color::Rgb(110, 110, 170);
+/// Live cells under the ghost, which stamping would reset.
+const OVERLAP_COLOR: Color = Color::Rgb(230, 60, 60);
+const SELECTION_TINT: Color = Color::Rgb(70, 70, 110);
+const MINIMAP_BG: Color = Color::Rgb(20, 20, 30);
+/// Minimap characters showing cells inside the view.
+const MINIMAP_VIEW: Color = Color::Rgb(60, 60, 120);
+
+/// Smallest terminal the grid and status bar are drawn in.
+const MIN_WIDTH: u16 = 24;
+const MIN_HEIGHT: u16 = 6;
+
 pub fn draw(frame: &mut Frame, app: &App) {
+    let area = frame.area();
+    let min_width = if app.library_open {
+        MIN_WIDTH + LIBRARY_COLUMNS as u16
+    } else {
+        MIN_WIDTH
+    };
+    if area.width < min_width || area.height < MIN_HEIGHT {
+        let message = format!(
+            "Terminal too small: {}x{}, need {}x{}",
+            area.width, area.height, min_width, MIN_HEIGHT
+        );
+        frame.render_widget(
+            Paragraph::new(message)
+                .wrap(Wrap { trim: true })
+                .alignment(Alignment::Center),
+            area,
+        );
+        return;
+    }
+
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([Constraint::Min(1), Constraint::Length(1)])
         .split(frame.area());
 
     let mut lines: Vec<Line> = Vec::new();
+    let region = app.view_region();
+    // Screen positions past the edges of a wrapping grid show the cells they wrap to
+    let at = |x: isize, y: isize| app.universe.wrap(x, y).unwrap_or((x, y));
+    let selection = app.selection();
+    // Background of dead cells, tinted inside the selection and where the
+    // engine computed the last step
+    let tint = |x: isize, y: isize| {
+        let (x, y) = at(x, y);
+        if selection.is_some_and(|s| s.contains(x, y)) {
+            SELECTION_TINT
+        } else if app.show_tiles && app.universe.active_at(x, y) {
+            TILE_TINT
+        } else {
+            Color::Reset
+        }
+    };
+    let (x_end, y_end) = (
+        region.x + region.width as isize,
+        region.y + region.height as isize,
+    );
 
-    if app.zoom == 0 {
-        // Half-block mode: 1 char per cell, 2 grid rows per terminal row
-        let visible_cols = app.view_width * 2;
-        let visible_rows = app.view_height * 2;
+    let ghost: HashSet<(isize, isize)> = app.ghost_cells().into_iter().collect();
+    let is_cursor =
+        |x: isize, y: isize| app.cursor_visible && x == app.cursor_x && y == app.cursor_y;
 
-        for row_pair in (0..visible_rows).step_by(2) {
-            let y_top = app.viewport_y + row_pair;
-            let y_bot = app.viewport_y + row_pair + 1;
+    if app.zoom == -1 {
+        // Braille mode: 2x4 cells per char, one dot each
+        for y_top in (region.y..y_end).step_by(4) {
             let mut spans = Vec::new();
-
-            for col in 0..visible_cols {
-                let x = app.viewport_x + col;
-                if x >= app.grid.width {
-                    break;
+            for x_left in (region.x..x_end).step_by(2) {
+                let mut bits = 0;
+                let mut oldest = 0;
+                let (mut overlap, mut cursor) = (false, false);
+                let mut bg = Color::Reset;
+                for dy in 0..4 {
+                    for dx in 0..2 {
+                        let (x, y) = (x_left + dx as isize, y_top + dy as isize);
+                        if x >= x_end || y >= y_end {
+                            continue;
+                        }
+                        if bg == Color::Reset {
+                            bg = tint(x, y);
+                        }
+                        let (x, y) = at(x, y);
+                        let age = app.universe.get(x, y);
+                        let ghosted = ghost.contains(&(x, y));
+                        if age > 0 || ghosted {
+                            bits |= minimap::braille_bit(dx, dy);
+                        }
+                        oldest = oldest.max(age);
+                        overlap |= ghosted && age > 0;
+                        cursor |= is_cursor(x, y);
+                    }
                 }
-                let age_top = if y_top < app.grid.height {
-                    app.grid.cells[y_top * app.grid.width + x]
+                let fg = if overlap {
+                    OVERLAP_COLOR
+                } else if oldest > 0 {
+                    age_color(oldest)
                 } else {
-                    0
+                    GHOST_COLOR
                 };
-                let age_bot = if y_bot < app.grid.height {
-                    app.grid.cells[y_bot * app.grid.width + x]
+                // The cursor lights up its whole character
+                let style = if cursor {
+                    Style::default().fg(Color::Black).bg(Color::LightGreen)
                 } else {
-                    0
+                    Style::default().fg(fg).bg(bg)
                 };
-
-                let is_cursor_top =
-                    app.cursor_visible && x == app.cursor_x && y_top == app.cursor_y;
-                let is_cursor_bot =
-                    app.cursor_visible && x == app.cursor_x && y_bot == app.cursor_y;
-
-                if is_cursor_top || is_cursor_bot {
-                    spans.push(Span::styled(
-                        "▀",
-                        Style::default()
-                            .fg(if is_cursor_top {
-                                Color::LightGreen
-                            } else if age_top > 0 {
-                                age_color(age_top)
-                            } else {
-                                Color::Reset
-                            })
-                            .bg(if is_cursor_bot {
-                                Color::LightGreen
-                            } else if age_bot > 0 {
-                                age_color(age_bot)
-                            } else {
-                                Color::Reset
-                            }),
-                    ));
-                } else {
-                    let (ch, style) = match (age_top > 0, age_bot > 0) {
-                        (true, true) => (
-                            "▀",
-                            Style::default()
-                                .fg(age_color(age_top))
-                                .bg(age_color(age_bot)),
-                        ),
-                        (true, false) => {
-                            ("▀", Style::default().fg(age_color(age_top)))
-                        }
-                        (false, true) => {
-                            ("▄", Style::default().fg(age_color(age_bot)))
-                        }
-                        (false, false) => (" ", Style::default()),
-                    };
-                    spans.push(Span::styled(ch, style));
-                }
+                spans.push(Span::styled(minimap::braille(bits).to_string(), style));
+            }
+            lines.push(Line::from(spans));
+        }
+    } else if app.zoom == 0 {
+        // Half-block mode: 1 char per cell, 2 grid rows per terminal row
+        let paint = |x: isize, y: isize| {
+            if y >= y_end {
+                return None;
+            }
+            let (x, y) = at(x, y);
+            let age = app.universe.get(x, y);
+            if is_cursor(x, y) {
+                Some(Color::LightGreen)
+            } else if ghost.contains(&(x, y)) {
+                Some(if age > 0 { OVERLAP_COLOR } else { GHOST_COLOR })
+            } else if age > 0 {
+                Some(age_color(age))
+            } else {
+                None
+            }
+        };
+        for y_top in (region.y..y_end).step_by(2) {
+            let y_bot = y_top + 1;
+            let mut spans = Vec::new();
+            for x in region.x..x_end {
+                let (ch, style) = match (paint(x, y_top), paint(x, y_bot)) {
+                    (Some(top), Some(bot)) => ("▀", Style::default().fg(top).bg(bot)),
+                    (Some(top), None) => ("▀", Style::default().fg(top).bg(tint(x, y_bot))),
+                    (None, Some(bot)) => ("▄", Style::default().fg(bot).bg(tint(x, y_top))),
+                    (None, None) => (" ", Style::default().bg(tint(x, y_top))),
+                };
+                spans.push(Span::styled(ch, style));
             }
             lines.push(Line::from(spans));
         }
     } else {
         let zoom = app.zoom as usize;
-        let visible_cols = app.view_width / zoom;
-        let visible_rows = app.view_height / zoom;
         let cell_str: String = "██".repeat(zoom);
+        let ghost_str: String = "▒▒".repeat(zoom);
         let empty_str: String = "  ".repeat(zoom);
-        for y in app.viewport_y..app.viewport_y + visible_rows {
+        for y in region.y..y_end {
             let mut spans = Vec::new();
-            for x in app.viewport_x..app.viewport_x + visible_cols {
-                let age = app.grid.cells[y * app.grid.width + x];
+            for x in region.x..x_end {
+                let (x, y) = at(x, y);
+                let age = app.universe.get(x, y);
                 let symbol = if age > 0 {
                     cell_str.clone()
+                } else if ghost.contains(&(x, y)) {
+                    ghost_str.clone()
                 } else {
                     empty_str.clone()
                 };
 
-                let style = if x == app.cursor_x && y == app.cursor_y && app.cursor_visible {
+                let fg = match (age > 0, ghost.contains(&(x, y))) {
+                    (true, true) => OVERLAP_COLOR,
+                    (true, false) => age_color(age),
+                    _ => GHOST_COLOR,
+                };
+                let style = if is_cursor(x, y) {
                     Style::default().bg(Color::LightGreen)
                 } else {
-                    Style::default().fg(age_color(age))
+                    Style::default().fg(fg).bg(tint(x, y))
                 };
                 spans.push(Span::styled(symbol, style));
             }
@@ -127,7 +201,23 @@ pub fn draw(frame: &mut Frame, app: &App) {
     let grid_block = Block::default().borders(Borders::ALL).title(" Petri ");
 
     let grid_widget = Paragraph::new(lines).block(grid_block);
-    frame.render_widget(grid_widget, chunks[0]);
+    let grid_area = if app.library_open {
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Min(1),
+                Constraint::Length(LIBRARY_COLUMNS as u16),
+            ])
+            .split(chunks[0]);
+        draw_library(frame, app, columns[1]);
+        columns[0]
+    } else {
+        chunks[0]
+    };
+    frame.render_widget(grid_widget, grid_area);
+    if app.show_minimap {
+        draw_minimap(frame, app, grid_area);
+    }
 
     let status = if app.paused { "PAUSED " } else { "RUNNING" };
     let status_style = if app.paused {
@@ -148,8 +238,8 @@ pub fn draw(frame: &mut Frame, app: &App) {
         .fg(Color::Rgb(200, 200, 230));
     let sep = Span::styled(" │ ", dim);
 
-    let cursor_info = if app.cursor_visible {
-        let cell_state = if app.grid.cells[app.cursor_y * app.grid.width + app.cursor_x] > 0 {
+    let mut cursor_info = if app.cursor_visible {
+        let cell_state = if app.universe.get(app.cursor_x, app.cursor_y) > 0 {
             "●"
         } else {
             "○"
@@ -165,49 +255,92 @@ pub fn draw(frame: &mut Frame, app: &App) {
         vec![]
     };
 
-    let mode_spans = if app.pattern_mode {
-        let pattern_list: String = patterns::ALL
-            .iter()
-            .enumerate()
-            .map(|(i, p)| format!("{}:{}", i + 1, p.name))
-            .collect::<Vec<_>>()
-            .join(" ");
-        vec![
-            sep.clone(),
-            Span::styled(
-                format!(" PATTERN: {}  Esc:cancel ", pattern_list),
-                Style::default()
-                    .bg(Color::Rgb(160, 100, 40))
-                    .fg(Color::White),
+    if let Some(s) = selection {
+        cursor_info.push(Span::styled(
+            format!(" SEL {}x{} ", s.width, s.height),
+            bright,
+        ));
+    }
+
+    let mut mode_spans = Vec::new();
+    if let Some(ghost) = app.ghost() {
+        let placing = Style::default()
+            .bg(Color::Rgb(160, 100, 40))
+            .fg(Color::White);
+        mode_spans.push(sep.clone());
+        mode_spans.push(Span::styled(
+            format!(
+                " PLACING {} {}  1-{}:pick  z/Z:rotate  m/M:mirror  Enter:stamp  Esc:cancel ",
+                ghost.name,
+                app.transform,
+                app.patterns.len().min(9)
             ),
-        ]
-    } else {
-        vec![]
-    };
+            placing,
+        ));
+        let overlap = app.ghost_overlap();
+        if overlap > 0 {
+            mode_spans.push(Span::styled(
+                format!(" OVERLAPS {} LIVE ", overlap),
+                Style::default().bg(OVERLAP_COLOR).fg(Color::White),
+            ));
+        }
+    }
 
     let mut spans = vec![
         Span::styled(format!(" {} ", status), status_style),
         sep.clone(),
         Span::styled(format!(" Gen: {} ", app.generation), bright),
         sep.clone(),
-        Span::styled(format!(" Alive: {} ", app.grid.population()), bright),
+        Span::styled(format!(" Alive: {} ", app.universe.population()), bright),
         sep.clone(),
         Span::styled(format!(" {}ms ", app.tick_rate.as_millis()), bright),
+        sep.clone(),
+        Span::styled(
+            format!(" {} {} ", app.universe.rule(), app.universe.shape()),
+            bright,
+        ),
     ];
+    if app.step_exp > 0 {
+        spans.push(sep.clone());
+        spans.push(Span::styled(format!(" Step: 2^{} ", app.step_exp), bright));
+    }
+    if let Some(seed) = app.seed {
+        spans.push(sep.clone());
+        spans.push(Span::styled(format!(" Seed: {} ", seed), bright));
+    }
     spans.extend(cursor_info);
     spans.extend(mode_spans);
 
+    if let Some(position) = app.timeline.position() {
+        spans.push(sep.clone());
+        spans.push(Span::styled(
+            format!(
+                " ◀ {} ▶ {}–{} ",
+                timeline_bar(position, app.timeline.len()),
+                app.timeline.generation(0),
+                app.timeline.generation(app.timeline.len() - 1)
+            ),
+            Style::default()
+                .bg(Color::Rgb(110, 70, 160))
+                .fg(Color::White),
+        ));
+    }
+
     if app.recording {
         spans.push(sep.clone());
         spans.push(Span::styled(
-            format!(" REC {}/{} ", app.recorded_frames.len(), crate::app::MAX_RECORDING_FRAMES),
+            format!(
+                " REC {}/{} ",
+                app.recorded_frames.len(),
+                app.recording_limit()
+            ),
             Style::default()
                 .bg(Color::Rgb(200, 40, 40))
                 .fg(Color::White),
         ));
     }
 
-    if let Some(ref msg) = app.last_export_msg {
+    if let Some(ref msg) = app.status_msg {
         spans.push(sep.clone());
         spans.push(Span::styled(
             format!(" {} ", msg),
@@ -218,13 +351,223 @@ pub fn draw(frame: &mut Frame, app: &App) {
     }
 
     spans.push(sep.clone());
-    spans.push(Span::styled(
-        " [spc] pause  [n] step  [r] rand  [tab] cursor  [±] speed  [\\[\\]] zoom  [c] clear  [q] quit ",
+    if let Some(ref prompt) = app.prompt {
+        spans.push(Span::styled(
+            format!(" {}: {}█ ", prompt.kind.label(), prompt.input),
+            Style::default()
+                .bg(Color::Rgb(160, 100, 40))
+                .fg(Color::White),
+        ));
+        spans.push(Span::styled(" Enter:apply  Esc:cancel ", dim));
+    } else {
+        spans.push(Span::styled(
+        " [spc] pause  [n] step  [r] rand  [tab] cursor  [±] speed  [\\[\\]] zoom  [R] rule  [t] topology  [w] save  [c] clear  [q] quit ",
         dim,
     ));
+    }
 
     let status_line = Line::from(spans);
     let status_widget =
         Paragraph::new(status_line).style(Style::default().bg(Color::Rgb(45, 45, 65)));
     frame.render_widget(status_widget, chunks[1]);
 }
+
+/// A fixed-width bar marking `position` among `len` timeline frames.
+fn timeline_bar(position: usize, len: usize) -> String {
+    const WIDTH: usize = 20;
+    let marker = position * (WIDTH - 1) / (len - 1).max(1);
+    (0..WIDTH)
+        .map(|i| match i.cmp(&marker) {
+            std::cmp::Ordering::Less => '━',
+            std::cmp::Ordering::Equal => '●',
+            std::cmp::Ordering::Greater => '─',
+        })
+        .collect()
+}
+
+/// Brightness of a minimap character by the fraction of its cells alive;
+/// a typical random soup reaches full brightness.
+fn density_color(density: f64) -> Color {
+    let t = (density * 4.0).min(1.0);
+    Color::Rgb(
+        (60.0 + 180.0 * t) as u8,
+        (110.0 + 140.0 * t) as u8,
+        (60.0 + 100.0 * t) as u8,
+    )
+}
+
+/// Draws the minimap over the top-left corner of the grid, with the cells
+/// in view highlighted.
+fn draw_minimap(frame: &mut Frame, app: &App, grid_area: Rect) {
+    let Some(map) = Minimap::new(app) else {
+        return;
+    };
+    let area = Rect {
+        x: grid_area.x + minimap::ORIGIN.0,
+        y: grid_area.y + minimap::ORIGIN.1,
+        width: map.cols as u16 + 2,
+        height: map.rows as u16 + 2,
+    }
+    .intersection(grid_area);
+    let lines: Vec<Line> = map
+        .render(app)
+        .into_iter()
+        .enumerate()
+        .map(|(row, chars)| {
+            let spans: Vec<Span> = chars
+                .into_iter()
+                .enumerate()
+                .map(|(col, (ch, density))| {
+                    let bg = if map.in_view(app, col, row) {
+                        MINIMAP_VIEW
+                    } else {
+                        MINIMAP_BG
+                    };
+                    Span::styled(
+                        ch.to_string(),
+                        Style::default().fg(density_color(density)).bg(bg),
+                    )
+                })
+                .collect();
+            Line::from(spans)
+        })
+        .collect();
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(" 1:{} ", map.scale)),
+        ),
+        area,
+    );
+}
+
+/// Search box, filtered pattern list and a thumbnail of the highlighted pattern.
+fn draw_library(frame: &mut Frame, app: &App, area: Rect) {
+    let library = &app.library;
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(3),
+            Constraint::Length(12),
+        ])
+        .split(area);
+
+    let search = Paragraph::new(format!("{}█", library.query)).block(
+        Block::default().borders(Borders::ALL).title(format!(
+            " Search {}/{} ",
+            library.matches.len(),
+            library.entries.len()
+        )),
+    );
+    frame.render_widget(search, rows[0]);
+
+    let name_width = LIBRARY_COLUMNS - 14;
+    let items: Vec<ListItem> = library
+        .matches
+        .iter()
+        .map(|&i| {
+            let entry = &library.entries[i];
+            let name: String = entry.pattern.name.chars().take(name_width).collect();
+            let period = entry.period.map(|p| format!("p{}", p)).unwrap_or_default();
+            ListItem::new(format!(
+                "{:<name_width$} {:>7} {:>4}",
+                name,
+                format!("{}x{}", entry.width, entry.height),
+                period
+            ))
+        })
+        .collect();
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Library  Enter:pick  Esc:close "),
+        )
+        .highlight_style(
+            Style::default()
+                .bg(Color::Rgb(160, 100, 40))
+                .fg(Color::White),
+        );
+    let mut state = ListState::default().with_selected(Some(library.selected));
+    frame.render_stateful_widget(list, rows[1], &mut state);
+
+    let (title, preview) = match library.selected_entry() {
+        Some(entry) => (
+            format!(
+                " {} · {} · {} cells ",
+                entry.pattern.name,
+                entry.category,
+                entry.pattern.cells.len()
+            ),
+            library::thumbnail(
+                &entry.pattern,
+                rows[2].width.saturating_sub(2) as usize,
+                rows[2].height.saturating_sub(2) as usize,
+            ),
+        ),
+        None => (" No matches ".to_string(), Vec::new()),
+    };
+    let preview: Vec<Line> = preview
+        .into_iter()
+        .map(|row| Line::styled(row, Style::default().fg(age_color(6))))
+        .collect();
+    frame.render_widget(
+        Paragraph::new(preview)
+            .alignment(Alignment::Center)
+            .block(Block::default().borders(Borders::ALL).title(title)),
+        rows[2],
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+
+    fn render(app: &mut App, cols: u16, rows: u16) -> String {
+        app.resize(cols, rows);
+        let mut terminal = Terminal::new(TestBackend::new(cols, rows)).unwrap();
+        terminal.draw(|frame| draw(frame, app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        buffer.content().iter().map(|cell| cell.symbol()).collect()
+    }
+
+    #[test]
+    fn tiny_terminals_get_a_message() {
+        let mut app = App::new(64, 64, 0, 0);
+        app.cursor_visible = true;
+        app.zoom = 0;
+        for (cols, rows) in [(0, 0), (1, 1), (3, 2), (23, 40), (80, 5)] {
+            let screen = render(&mut app, cols, rows);
+            assert!(cols < 10 || screen.contains("small"), "{}x{}", cols, rows);
+        }
+        assert!(render(&mut app, 24, 6).contains("PAUSED"));
+        app.library_open = true;
+        assert!(render(&mut app, 40, 20).contains("small"));
+        assert!(render(&mut app, 80, 20).contains("Library"));
+    }
+
+    #[test]
+    fn braille_mode_draws_eight_cells_per_character() {
+        let mut app = App::new(64, 64, 0, 0);
+        app.zoom = -1;
+        for (x, y) in [(0, 0), (1, 1), (1, 3), (4, 4)] {
+            app.universe.set(x, y, 1);
+        }
+        let screen = render(&mut app, 40, 10);
+        let rows: Vec<Vec<char>> = screen
+            .chars()
+            .collect::<Vec<_>>()
+            .chunks(40)
+            .map(<[char]>::to_vec)
+            .collect();
+        assert_eq!(rows[1][1], '\u{2891}');
+        assert_eq!(rows[2][3], '\u{2801}');
+        assert_eq!(rows[1][2], '\u{2800}');
+        // 38 columns of 2 cells and 7 rows of 4
+        assert_eq!(app.visible_size(), (76, 28));
+    }
+}
@@ -0,0 +1,216 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::grid::Grid;
+use crate::hashlife::HashLife;
+use crate::plane::Plane;
+use crate::rule::Rule;
+use crate::topology::Topology;
+
+/// Unbounded universes hold cells within `-MAX_COORD..MAX_COORD` on both axes,
+/// well clear of overflow.
+pub const MAX_COORD: isize = 1 << 60;
+
+/// Axis-aligned rectangle of cells.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Region {
+    pub x: isize,
+    pub y: isize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Region {
+    pub fn contains(&self, x: isize, y: isize) -> bool {
+        (self.x..self.x + self.width as isize).contains(&x)
+            && (self.y..self.y + self.height as isize).contains(&y)
+    }
+
+    /// Overlap of two regions, if any.
+    pub fn intersect(&self, other: &Region) -> Option<Region> {
+        let x0 = self.x.max(other.x);
+        let y0 = self.y.max(other.y);
+        let x1 = (self.x + self.width as isize).min(other.x + other.width as isize);
+        let y1 = (self.y + self.height as isize).min(other.y + other.height as isize);
+        (x1 > x0 && y1 > y0).then(|| Region {
+            x: x0,
+            y: y0,
+            width: (x1 - x0) as usize,
+            height: (y1 - y0) as usize,
+        })
+    }
+
+    /// Smallest region containing all of `cells`.
+    pub fn bounding(cells: impl IntoIterator<Item = (isize, isize)>) -> Option<Region> {
+        let mut cells = cells.into_iter();
+        let (x, y) = cells.next()?;
+        let (x0, y0, x1, y1) = cells.fold((x, y, x, y), |(x0, y0, x1, y1), (x, y)| {
+            (x0.min(x), y0.min(y), x1.max(x), y1.max(y))
+        });
+        Some(Region {
+            x: x0,
+            y: y0,
+            width: (x1 - x0 + 1) as usize,
+            height: (y1 - y0 + 1) as usize,
+        })
+    }
+}
+
+/// Which simulation backend holds the cells.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Engine {
+    /// Fixed-size grid with configurable edges.
+    #[default]
+    Grid,
+    /// Unbounded plane of 32×32 tiles, allocated where cells are alive.
+    Tiled,
+    /// Unbounded memoized quadtree that can skip ahead 2^k generations at once.
+    HashLife,
+}
+
+pub const ENGINES: &[Engine] = &[Engine::Grid, Engine::Tiled, Engine::HashLife];
+
+impl Engine {
+    /// An empty universe of this kind; `grid_size` only applies to `Grid`.
+    pub fn create(self, grid_size: (usize, usize)) -> Box<dyn Universe> {
+        match self {
+            Engine::Grid => Box::new(Grid::new(grid_size.0, grid_size.1)),
+            Engine::Tiled => Box::new(Plane::new()),
+            Engine::HashLife => Box::new(HashLife::new()),
+        }
+    }
+
+    pub fn next(self) -> Engine {
+        let idx = ENGINES.iter().position(|&e| e == self).unwrap_or(0);
+        ENGINES[(idx + 1) % ENGINES.len()]
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Engine::Grid => "grid",
+            Engine::Tiled => "tiled",
+            Engine::HashLife => "hashlife",
+        }
+    }
+}
+
+impl fmt::Display for Engine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Engine {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ENGINES
+            .iter()
+            .copied()
+            .find(|e| e.name().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| {
+                let names: Vec<_> = ENGINES.iter().map(|e| e.name()).collect();
+                format!("unknown engine '{}' (expected {})", s, names.join(", "))
+            })
+    }
+}
+
+/// A simulation backend. Coordinates are signed so unbounded universes can
+/// extend in every direction; finite ones occupy `0..width` × `0..height`.
+pub trait Universe {
+    /// Age of the cell at `(x, y)`, 0 if dead or outside a finite universe.
+    fn get(&self, x: isize, y: isize) -> u16;
+
+    /// Sets the age of a cell; ignored outside a finite universe.
+    fn set(&mut self, x: isize, y: isize, age: u16);
+
+    /// Canonical position of a possibly out-of-range coordinate, following the
+    /// edges of a finite universe; `None` if it falls off a bounded plane.
+    fn wrap(&self, x: isize, y: isize) -> Option<(isize, isize)>;
+
+    fn step(&mut self);
+
+    /// Advances any number of generations; backends that can skip ahead override this.
+    fn step_by(&mut self, generations: u64) {
+        for _ in 0..generations {
+            self.step();
+        }
+    }
+
+    fn population(&self) -> usize;
+
+    fn clear(&mut self);
+
+    /// Live cells with their ages, in no particular order.
+    fn live_cells(&self) -> Vec<(isize, isize, u16)>;
+
+    /// Extent of a finite universe, `None` if unbounded.
+    fn bounds(&self) -> Option<Region>;
+
+    fn rule(&self) -> Rule;
+
+    /// Switches rule, failing if this backend cannot simulate it.
+    fn set_rule(&mut self, rule: Rule) -> Result<(), String>;
+
+    /// Edge topology of a finite universe, `None` if unbounded.
+    fn topology(&self) -> Option<Topology>;
+
+    /// Switches edge topology; ignored by unbounded universes.
+    fn set_topology(&mut self, topology: Topology);
+
+    fn engine(&self) -> Engine;
+
+    /// Whether to keep per-cell ages. Backends that can step faster without them
+    /// may drop them while off; ages restart at 1 when tracking resumes.
+    fn set_track_ages(&mut self, _track: bool) {}
+
+    /// Whether the backend computed the cell's neighborhood in the last step, for
+    /// the tile overlay; backends that always compute everything return false.
+    fn active_at(&self, _x: isize, _y: isize) -> bool {
+        false
+    }
+
+    /// Number of threads a backend may use to step; ignored by single-threaded ones.
+    fn set_threads(&mut self, _threads: usize) {}
+
+    /// Packed rows of a finite universe and the words in each, column `x` of a
+    /// row in bit `x % 64` of its word `x / 64`; `None` if cells are not packed.
+    fn packed_rows(&self) -> Option<(&[u64], usize)> {
+        None
+    }
+
+    /// Fills `region` with live cells at the given density, leaving cells
+    /// outside it untouched.
+    fn randomize(&mut self, region: Region, density: f64, rng: &mut fastrand::Rng) {
+        for y in region.y..region.y + region.height as isize {
+            for x in region.x..region.x + region.width as isize {
+                let alive = rng.f64() < density;
+                self.set(x, y, alive as u16);
+            }
+        }
+    }
+
+    /// Ages of the cells in `region`, row-major.
+    fn snapshot(&self, region: Region) -> Vec<u16> {
+        let mut cells = Vec::with_capacity(region.width * region.height);
+        for y in region.y..region.y + region.height as isize {
+            for x in region.x..region.x + region.width as isize {
+                cells.push(self.get(x, y));
+            }
+        }
+        cells
+    }
+
+    /// Smallest region containing every live cell.
+    fn bounding_box(&self) -> Option<Region> {
+        Region::bounding(self.live_cells().into_iter().map(|(x, y, _)| (x, y)))
+    }
+
+    /// Short description of the universe's shape for the status bar.
+    fn shape(&self) -> String {
+        match self.topology() {
+            Some(topology) => topology.to_string(),
+            None => format!("infinite {}", self.engine()),
+        }
+    }
+}